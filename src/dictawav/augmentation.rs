@@ -0,0 +1,314 @@
+extern crate rand;
+extern crate rustfft;
+
+use std::f64;
+use self::rand::{Rng, SeedableRng, StdRng};
+use self::rustfft::algorithm::Radix4;
+use self::rustfft::FFT;
+use self::rustfft::num_complex::Complex;
+use self::rustfft::num_traits::Zero;
+
+/// Decay of 60 dB, as a natural logarithm of the amplitude
+const RT60_LOG_DECAY: f64 = 6.907_755_278_982_137;
+/// Length of the windows overlapped to change the tempo, in seconds
+const STRETCH_WINDOW_SECONDS: f64 = 0.03;
+
+/// Spectrum of the noise added to a recording
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseColor {
+    White,
+    /// Power falling 3 dB per octave
+    Pink,
+}
+
+/// A change made to a recording, its amount drawn between the given bounds for every copy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Augmentation {
+    /// Plays the recording faster or slower, changing its duration and pitch together
+    Speed { min_factor: f64, max_factor: f64 },
+    /// Changes the duration keeping the pitch, a factor above one making it shorter
+    Tempo { min_factor: f64, max_factor: f64 },
+    /// Changes the pitch keeping the duration
+    PitchShift { min_semitones: f64, max_semitones: f64 },
+    /// Adds noise with the signal to noise ratio, in dB, drawn
+    Noise { color: NoiseColor, min_snr_db: f64, max_snr_db: f64 },
+    Gain { min_db: f64, max_db: f64 },
+    /// Delays or advances the recording up to the given seconds, keeping its length
+    TimeShift { max_seconds: f64 },
+    /// Convolves the recording with an exponentially decaying noise, lasting the reverberation time
+    /// drawn, in seconds
+    Reverb { min_rt60: f64, max_rt60: f64 },
+}
+
+impl Augmentation {
+    fn bounds(&self) -> (f64, f64) {
+        match *self {
+            Augmentation::Speed { min_factor, max_factor } => (min_factor, max_factor),
+            Augmentation::Tempo { min_factor, max_factor } => (min_factor, max_factor),
+            Augmentation::PitchShift { min_semitones, max_semitones } => (min_semitones, max_semitones),
+            Augmentation::Noise { min_snr_db, max_snr_db, .. } => (min_snr_db, max_snr_db),
+            Augmentation::Gain { min_db, max_db } => (min_db, max_db),
+            Augmentation::TimeShift { max_seconds } => (-max_seconds, max_seconds),
+            Augmentation::Reverb { min_rt60, max_rt60 } => (min_rt60, max_rt60),
+        }
+    }
+
+    fn apply(&self, samples: &[f64], sample_rate: usize, random_generator: &mut StdRng) -> Vec<f64> {
+        let (min, max) = self.bounds();
+        let amount = if min < max { random_generator.gen_range(min, max) } else { min };
+
+        match *self {
+            Augmentation::Speed { .. } => resample(samples, amount),
+            Augmentation::Tempo { .. } => stretch(samples, amount, sample_rate),
+            Augmentation::PitchShift { .. } => pitch_shift(samples, amount, sample_rate),
+            Augmentation::Noise { color, .. } => add_noise(samples, color, amount, random_generator),
+            Augmentation::Gain { .. } => samples.iter().map(|sample| sample * 10f64.powf(amount / 20f64)).collect(),
+            Augmentation::TimeShift { .. } => time_shift(samples, (amount * sample_rate as f64).round() as isize),
+            Augmentation::Reverb { .. } => reverb(samples, amount, sample_rate, random_generator),
+        }
+    }
+}
+
+/// Makes extra copies of recordings, each one going through every augmentation in order
+///
+/// Copies only depend on the seed, the recording and the key they are asked with, so the same
+/// copies can be made again to forget them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Augmenter {
+    augmentations: Vec<Augmentation>,
+    copies: usize,
+    seed: usize,
+}
+
+impl Augmenter {
+    pub fn new(augmentations: Vec<Augmentation>, copies: usize, seed: usize) -> Augmenter {
+        for augmentation in &augmentations {
+            let (min, max) = augmentation.bounds();
+            if min.is_nan() || max.is_nan() || min > max {
+                panic!("Augmentation Error: Invalid bounds for {:?}!", augmentation);
+            }
+            let valid_amounts = match *augmentation {
+                Augmentation::Speed { .. } | Augmentation::Tempo { .. } => min > 0f64,
+                Augmentation::Reverb { .. } => min >= 0f64,
+                _ => true,
+            };
+            if !valid_amounts {
+                panic!("Augmentation Error: {:?} needs positive amounts!", augmentation);
+            }
+        }
+
+        Augmenter {
+            augmentations,
+            copies,
+            seed,
+        }
+    }
+
+    pub fn copies(&self) -> usize {
+        self.copies
+    }
+
+    /// Augmented copies of a recording, the same for the same key
+    pub fn augment(&self, samples: &[f64], sample_rate: usize, key: u64) -> Vec<Vec<f64>> {
+        let mut random_generator: StdRng = SeedableRng::from_seed(&[self.seed, key as usize, (key >> 32) as usize][..]);
+
+        (0..self.copies).map(
+            |_| self.augmentations.iter().fold(
+                samples.to_vec(),
+                |augmented, augmentation| augmentation.apply(&augmented, sample_rate, &mut random_generator)
+            )
+        ).collect()
+    }
+}
+
+/// Reads the samples `factor` times faster, interpolating linearly
+fn resample(samples: &[f64], factor: f64) -> Vec<f64> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let length = (samples.len() as f64 / factor).floor() as usize;
+
+    (0..length).map(
+        |index| {
+            let position = index as f64 * factor;
+            let before = position.floor() as usize;
+            let after = (before + 1usize).min(samples.len() - 1usize);
+            let fraction = position - before as f64;
+            samples[before] * (1f64 - fraction) + samples[after] * fraction
+        }
+    ).collect()
+}
+
+/// Overlaps Hann windows read `factor` times faster than they are written
+fn stretch(samples: &[f64], factor: f64, sample_rate: usize) -> Vec<f64> {
+    let window_size = ((STRETCH_WINDOW_SECONDS * sample_rate as f64) as usize).max(4usize) & !1usize;
+    let hop_size = window_size / 2usize;
+    let length = (samples.len() as f64 / factor).floor() as usize;
+    let window: Vec<f64> = (0..window_size).map(
+        |index| 0.5f64 - 0.5f64 * (2f64 * f64::consts::PI * index as f64 / window_size as f64).cos()
+    ).collect();
+
+    let mut stretched = vec![0f64; length];
+    let mut window_sums = vec![0f64; length];
+    for output_start in (0..length).step_by(hop_size) {
+        let input_start = (output_start as f64 * factor).round() as usize;
+        for (index, weight) in window.iter().enumerate() {
+            if output_start + index >= length {
+                break;
+            }
+            let sample = samples.get(input_start + index).cloned().unwrap_or(0f64);
+            stretched[output_start + index] += sample * weight;
+            window_sums[output_start + index] += weight;
+        }
+    }
+
+    stretched.iter()
+             .zip(window_sums.iter())
+             .map(|(sample, window_sum)| if *window_sum > 1e-6 { sample / window_sum } else { *sample })
+             .collect()
+}
+
+fn pitch_shift(samples: &[f64], semitones: f64, sample_rate: usize) -> Vec<f64> {
+    let ratio = 2f64.powf(semitones / 12f64);
+    let mut shifted = resample(&stretch(samples, 1f64 / ratio, sample_rate), ratio);
+    shifted.resize(samples.len(), 0f64);
+    shifted
+}
+
+fn add_noise(samples: &[f64], color: NoiseColor, snr_db: f64, random_generator: &mut StdRng) -> Vec<f64> {
+    mix_at_snr(samples, &noise(samples.len(), color, random_generator), snr_db)
+}
+
+/// Uniform white noise, or pink noise from it
+pub fn noise(length: usize, color: NoiseColor, random_generator: &mut StdRng) -> Vec<f64> {
+    let mut noise: Vec<f64> = (0..length).map(|_| random_generator.gen_range(-1f64, 1f64)).collect();
+    if color == NoiseColor::Pink {
+        // Paul Kellet's economy filter
+        let (mut b0, mut b1, mut b2) = (0f64, 0f64, 0f64);
+        for sample in &mut noise {
+            b0 = 0.99765f64 * b0 + *sample * 0.0990460f64;
+            b1 = 0.96300f64 * b1 + *sample * 0.2965164f64;
+            b2 = 0.57000f64 * b2 + *sample * 1.0526913f64;
+            *sample = b0 + b1 + b2 + *sample * 0.1848f64;
+        }
+    }
+    noise
+}
+
+/// Adds noise of the same length scaled to the signal to noise ratio, in dB, silence staying clean
+pub fn mix_at_snr(samples: &[f64], noise: &[f64], snr_db: f64) -> Vec<f64> {
+    let signal_power = power(samples);
+    let noise_power = power(noise);
+    if signal_power == 0f64 || noise_power == 0f64 {
+        return samples.to_vec();
+    }
+    let scale = (signal_power / (noise_power * 10f64.powf(snr_db / 10f64))).sqrt();
+
+    samples.iter().zip(noise).map(|(sample, noise)| sample + noise * scale).collect()
+}
+
+/// Moves the samples later by `shift`, or earlier when negative, filling with silence
+fn time_shift(samples: &[f64], shift: isize) -> Vec<f64> {
+    let length = samples.len() as isize;
+    (0..length).map(|index| if index - shift >= 0 && index - shift < length { samples[(index - shift) as usize] } else { 0f64 })
+               .collect()
+}
+
+/// Convolves with a synthetic impulse response, keeping the length and power of the samples
+fn reverb(samples: &[f64], rt60: f64, sample_rate: usize, random_generator: &mut StdRng) -> Vec<f64> {
+    let response_length = (rt60 * sample_rate as f64) as usize;
+    if samples.is_empty() || response_length < 2usize {
+        return samples.to_vec();
+    }
+    let mut response: Vec<f64> = (0..response_length).map(
+        |index| random_generator.gen_range(-1f64, 1f64) * (-RT60_LOG_DECAY * index as f64 / response_length as f64).exp()
+    ).collect();
+    // Direct sound
+    response[0] = 1f64;
+
+    let mut reverberated = convolve(samples, &response);
+    reverberated.truncate(samples.len());
+
+    let reverberated_power = power(&reverberated);
+    if reverberated_power > 0f64 {
+        let scale = (power(samples) / reverberated_power).sqrt();
+        reverberated.iter_mut().for_each(|sample| *sample *= scale);
+    }
+    reverberated
+}
+
+fn convolve(first: &[f64], second: &[f64]) -> Vec<f64> {
+    let length = first.len() + second.len() - 1usize;
+    let size = length.next_power_of_two();
+
+    let spectrum = |values: &[f64]| {
+        let mut input: Vec<Complex<f64>> = values.iter().map(|value| Complex::new(*value, 0f64)).collect();
+        input.resize(size, Complex::zero());
+        let mut output = vec![Complex::zero(); size];
+        Radix4::new(size, false).process(&mut input, &mut output);
+        output
+    };
+    let mut product: Vec<Complex<f64>> = spectrum(first).into_iter().zip(spectrum(second)).map(|(a, b)| a * b).collect();
+
+    let mut convolved = vec![Complex::zero(); size];
+    Radix4::new(size, true).process(&mut product, &mut convolved);
+    convolved.iter().take(length).map(|value| value.re / size as f64).collect()
+}
+
+pub fn power(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0f64;
+    }
+    samples.iter().map(|sample| sample * sample).sum::<f64>() / samples.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(length: usize) -> Vec<f64> {
+        (0..length).map(|index| (index as f64 * 0.05f64).sin()).collect()
+    }
+
+    #[test]
+    fn same_seed_and_key_give_same_copies() {
+        let augmenter = Augmenter::new(vec![
+            Augmentation::Speed { min_factor: 0.9f64, max_factor: 1.1f64 },
+            Augmentation::Tempo { min_factor: 0.9f64, max_factor: 1.1f64 },
+            Augmentation::PitchShift { min_semitones: -2f64, max_semitones: 2f64 },
+            Augmentation::Noise { color: NoiseColor::Pink, min_snr_db: 10f64, max_snr_db: 20f64 },
+            Augmentation::Gain { min_db: -6f64, max_db: 6f64 },
+            Augmentation::TimeShift { max_seconds: 0.01f64 },
+            Augmentation::Reverb { min_rt60: 0.05f64, max_rt60: 0.1f64 },
+        ], 3usize, 7usize);
+        let samples = tone(4000usize);
+
+        let copies = augmenter.augment(&samples, 8000usize, 1u64);
+        assert_eq!(copies.len(), 3usize);
+        assert_eq!(copies, augmenter.augment(&samples, 8000usize, 1u64));
+        assert!(copies != augmenter.augment(&samples, 8000usize, 2u64));
+        assert!(copies[0] != copies[1]);
+        assert!(copies.iter().all(|copy| copy.iter().all(|sample| sample.is_finite())));
+    }
+
+    #[test]
+    fn augmentations_keep_their_promises() {
+        let samples = tone(8000usize);
+        let mut random_generator: StdRng = SeedableRng::from_seed(&[1usize][..]);
+
+        assert_eq!(resample(&samples, 2f64).len(), 4000usize);
+        assert_eq!(stretch(&samples, 0.5f64, 8000usize).len(), 16000usize);
+        assert_eq!(pitch_shift(&samples, 3f64, 8000usize).len(), 8000usize);
+        assert_eq!(time_shift(&[1f64, 2f64, 3f64], 1isize), vec![0f64, 1f64, 2f64]);
+        assert_eq!(time_shift(&[1f64, 2f64, 3f64], -1isize), vec![2f64, 3f64, 0f64]);
+        assert_eq!(convolve(&[1f64, 2f64], &[1f64, 1f64, 1f64]).iter().map(|value| value.round()).collect::<Vec<f64>>(),
+                   vec![1f64, 3f64, 3f64, 2f64]);
+
+        let noisy = add_noise(&samples, NoiseColor::White, 10f64, &mut random_generator);
+        let noise: Vec<f64> = noisy.iter().zip(samples.iter()).map(|(noisy, sample)| noisy - sample).collect();
+        assert!((10f64 * (power(&samples) / power(&noise)).log10() - 10f64).abs() < 1e-9);
+
+        let reverberated = reverb(&samples, 0.2f64, 8000usize, &mut random_generator);
+        assert!((power(&reverberated) - power(&samples)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path;
+
+/// 8 bit grayscale image, stored row after row from the top
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// Scales the values linearly so the smallest is black and the largest is white
+    pub fn from_values(width: usize, height: usize, values: &[f64]) -> GrayImage {
+        if values.len() != width * height {
+            panic!("Diagnostics Error: Image size doesn't match the number of values!");
+        }
+
+        let minimum = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let maximum = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let range = maximum - minimum;
+
+        let pixels = values.iter()
+                           .map(
+                               |value|
+                                   if range > 0f64 {
+                                       ((*value - minimum) / range * 255f64).round() as u8
+                                   } else {
+                                       0u8
+                                   }
+                           ).collect();
+
+        GrayImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Writes the image as a binary PGM, which most image viewers open
+    pub fn write_pgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)?;
+        writer.flush()
+    }
+
+    pub fn save_pgm<P: AsRef<path::Path>>(&self, image_file: P) -> io::Result<()> {
+        self.write_pgm(io::BufWriter::new(fs::File::create(image_file)?))
+    }
+}
+
+/// Draws values laid out like a painted canvas
+///
+/// Each region of `kernel_count` values becomes a tile of nearly square shape, tiles of the same
+/// output block go side by side from the first time segment to the last, and output blocks are
+/// stacked from the top. Padding at the end of each tile counts as zero.
+// usize::div_ceil needs a newer compiler than the crate otherwise does
+#[allow(clippy::manual_div_ceil)]
+pub fn retina_image(values: &[f64], kernel_count: usize, time_segments: usize, output_factor: usize) -> GrayImage {
+    if values.len() != kernel_count * time_segments * output_factor {
+        panic!("Diagnostics Error: Values don't match the canvas size!");
+    }
+
+    let tile_width = (kernel_count as f64).sqrt().ceil() as usize;
+    let tile_height = (kernel_count + tile_width - 1usize) / tile_width;
+    let width = tile_width * time_segments;
+    let height = tile_height * output_factor;
+
+    let mut pixel_values = vec![0f64; width * height];
+    for (region_index, region) in values.chunks(kernel_count).enumerate() {
+        let block = region_index / time_segments;
+        let segment = region_index % time_segments;
+        for (kernel_index, value) in region.iter().enumerate() {
+            let row = block * tile_height + kernel_index / tile_width;
+            let column = segment * tile_width + kernel_index % tile_width;
+            pixel_values[row * width + column] = *value;
+        }
+    }
+
+    GrayImage::from_values(width, height, &pixel_values)
+}
+
+/// Draws frames of features as a spectrogram, time going right and the first feature at the bottom
+pub fn features_image(frames: &[Vec<f64>]) -> GrayImage {
+    let width = frames.len();
+    let height = frames.first().map_or(0usize, |frame| frame.len());
+
+    let mut pixel_values = vec![0f64; width * height];
+    for (column, frame) in frames.iter().enumerate() {
+        if frame.len() != height {
+            panic!("Diagnostics Error: Trying to draw frames with different dimension!");
+        }
+        for (feature_index, value) in frame.iter().enumerate() {
+            pixel_values[(height - 1usize - feature_index) * width + column] = *value;
+        }
+    }
+
+    GrayImage::from_values(width, height, &pixel_values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_scaled_pgm() {
+        let image = GrayImage::from_values(2usize, 2usize, &[1f64, 3f64, 2f64, 1f64]);
+        assert_eq!(image.pixels(), &[0u8, 255u8, 128u8, 0u8]);
+
+        let mut buffer = Vec::new();
+        image.write_pgm(&mut buffer).unwrap();
+        assert_eq!(buffer, b"P5\n2 2\n255\n\x00\xff\x80\x00".to_vec());
+    }
+
+    #[test]
+    fn layouts() {
+        // 3 kernels, 2 time segments and 2 blocks, tiles of 2x2 pixels
+        let values: Vec<f64> = (1..13).map(|value| value as f64).collect();
+        let image = retina_image(&values, 3usize, 2usize, 2usize);
+        assert_eq!((image.width(), image.height()), (4usize, 4usize));
+
+        let expected = GrayImage::from_values(4usize, 4usize, &[
+            1f64, 2f64, 4f64, 5f64,
+            3f64, 0f64, 6f64, 0f64,
+            7f64, 8f64, 10f64, 11f64,
+            9f64, 0f64, 12f64, 0f64,
+        ]);
+        assert_eq!(image, expected);
+
+        let image = features_image(&[vec![0f64, 2f64], vec![1f64, 3f64]]);
+        assert_eq!(image, GrayImage::from_values(2usize, 2usize, &[2f64, 3f64, 0f64, 1f64]));
+    }
+}
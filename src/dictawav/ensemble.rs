@@ -0,0 +1,224 @@
+use std::collections::hash_map::HashMap;
+use std::path;
+use super::DictaWav;
+use super::wisard::{ForgetError, Prediction};
+
+/// How the members of an ensemble combine their answers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnsembleVoting {
+    /// Each member gives one vote to the class it decided on, undecided members don't vote
+    MajorityVote,
+    /// Probabilities of every class averaged over the members
+    ScoreAveraging,
+    /// Each member votes for the class it decided on with its confidence
+    ConfidenceWeighting,
+}
+
+/// Answer of an ensemble, with the answers of its members
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnsemblePrediction {
+    pub class_name: String,
+    /// Share of the votes, average probability or summed confidence of the class, by voting
+    pub score: f64,
+    /// In the order the members were given
+    pub members: Vec<Prediction>,
+}
+
+/// Models trained on the same files, each one with its own kernels and address mapping
+///
+/// Members are usually built by the same code with different seeds given to `DictaWav::set_seed`.
+pub struct Ensemble {
+    members: Vec<DictaWav>,
+    voting: EnsembleVoting,
+}
+
+impl Ensemble {
+    pub fn new(members: Vec<DictaWav>, voting: EnsembleVoting) -> Ensemble {
+        if members.is_empty() {
+            panic!("Ensemble Error: An ensemble needs at least one member!");
+        }
+
+        Ensemble {
+            members,
+            voting,
+        }
+    }
+
+    pub fn set_voting(&mut self, voting: EnsembleVoting) {
+        self.voting = voting;
+    }
+
+    pub fn members(&self) -> &[DictaWav] {
+        &self.members
+    }
+
+    pub fn train<P: AsRef<path::Path>>(&mut self, wav_file: P, class_name: String) {
+        for member in &mut self.members {
+            member.train(wav_file.as_ref(), class_name.clone());
+        }
+    }
+
+    /// Removes a trained file from every member, training the members that already forgot it
+    /// again when one of them fails, so all of them keep knowing the same files
+    pub fn forget<P: AsRef<path::Path>>(&mut self, wav_file: P, class_name: &str) -> Result<(), ForgetError> {
+        for index in 0..self.members.len() {
+            if let Err(error) = self.members[index].forget(wav_file.as_ref(), class_name) {
+                for member in &mut self.members[..index] {
+                    member.train(wav_file.as_ref(), String::from(class_name));
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn classify<P: AsRef<path::Path>>(&mut self, wav_file: P) -> String {
+        self.predict(wav_file).class_name
+    }
+
+    /// Combines the predictions of the members, ties going to the first class name
+    pub fn predict<P: AsRef<path::Path>>(&mut self, wav_file: P) -> EnsemblePrediction {
+        let members = self.members
+                          .iter_mut()
+                          .map(|member| member.predict(wav_file.as_ref()))
+                          .collect();
+        combine(self.voting, members)
+    }
+
+    /// Combines the predictions of the members for mono samples instead of a file
+    pub fn predict_audio(&mut self, audio_data: &[f64], sample_rate: usize) -> EnsemblePrediction {
+        let members = self.members
+                          .iter_mut()
+                          .map(|member| member.predict_audio(audio_data.to_vec(), sample_rate))
+                          .collect();
+        combine(self.voting, members)
+    }
+
+    /// Heap memory taken by the WiSARDs of every member
+    pub fn memory_bytes(&self) -> usize {
+        self.members.iter().map(|member| member.memory_bytes()).sum()
+    }
+}
+
+/// Scores every class by the voting rule, ties going to the first class name
+fn combine(voting: EnsembleVoting, members: Vec<Prediction>) -> EnsemblePrediction {
+    let members_count = members.len() as f64;
+
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for prediction in &members {
+        match voting {
+            EnsembleVoting::ScoreAveraging => {
+                for (class_name, probability) in &prediction.probabilities {
+                    *scores.entry(class_name.clone()).or_insert(0f64) += probability / members_count;
+                }
+            }
+            // Undecided members name no trained class
+            _ if !prediction.probabilities.contains_key(&prediction.class_name) => {}
+            EnsembleVoting::MajorityVote => {
+                *scores.entry(prediction.class_name.clone()).or_insert(0f64) += 1f64 / members_count;
+            }
+            EnsembleVoting::ConfidenceWeighting => {
+                *scores.entry(prediction.class_name.clone()).or_insert(0f64) += prediction.confidence;
+            }
+        }
+    }
+
+    let best_class = scores.into_iter()
+                           .filter(|&(_, score)| score > 0f64)
+                           .min_by(|first, second| second.1.total_cmp(&first.1).then_with(|| first.0.cmp(&second.0)));
+    let (class_name, score) = best_class.unwrap_or_else(|| (String::from("Not enough confidence to decide"), 0f64));
+
+    EnsemblePrediction {
+        class_name,
+        score,
+        members,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::FrontEnd;
+
+    fn prediction(class_name: &str, confidence: f64, probabilities: &[(&str, f64)]) -> Prediction {
+        Prediction {
+            class_name: String::from(class_name),
+            probability: probabilities.iter().find(|&&(name, _)| name == class_name).map_or(0f64, |&(_, probability)| probability),
+            confidence,
+            tied_classes: Vec::new(),
+            probabilities: probabilities.iter().map(|&(name, probability)| (String::from(name), probability)).collect(),
+        }
+    }
+
+    fn undecided(probabilities: &[(&str, f64)]) -> Prediction {
+        prediction("Not enough confidence to decide", 0f64, probabilities)
+    }
+
+    #[test]
+    fn each_voting_rule_weighs_the_members() {
+        let members = vec![
+            prediction("a", 0.5f64, &[("a", 0.8f64), ("b", 0.4f64)]),
+            prediction("b", 0.9f64, &[("a", 0.3f64), ("b", 0.9f64)]),
+            prediction("a", 0.1f64, &[("a", 0.5f64), ("b", 0.45f64)]),
+        ];
+
+        let majority = combine(EnsembleVoting::MajorityVote, members.clone());
+        assert_eq!(majority.class_name, "a");
+        assert!((majority.score - 2f64 / 3f64).abs() < 1e-12);
+        assert_eq!(majority.members, members);
+
+        let averaged = combine(EnsembleVoting::ScoreAveraging, members.clone());
+        assert_eq!(averaged.class_name, "b");
+        assert!((averaged.score - 1.75f64 / 3f64).abs() < 1e-12);
+
+        let weighted = combine(EnsembleVoting::ConfidenceWeighting, members);
+        assert_eq!(weighted.class_name, "b");
+        assert!((weighted.score - 0.9f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn undecided_members_only_count_in_score_averaging() {
+        let members = vec![
+            prediction("b", 0.2f64, &[("a", 0.1f64), ("b", 0.3f64)]),
+            undecided(&[("a", 0.9f64), ("b", 0f64)]),
+        ];
+
+        for voting in &[EnsembleVoting::MajorityVote, EnsembleVoting::ConfidenceWeighting] {
+            assert_eq!(combine(*voting, members.clone()).class_name, "b");
+        }
+        assert_eq!(combine(EnsembleVoting::ScoreAveraging, members).class_name, "a");
+
+        let nobody_decided = combine(EnsembleVoting::MajorityVote, vec![undecided(&[("a", 0.5f64)]), undecided(&[])]);
+        assert_eq!(nobody_decided.class_name, "Not enough confidence to decide");
+        assert_eq!(nobody_decided.score, 0f64);
+    }
+
+    #[test]
+    fn failed_forget_leaves_every_member_trained() {
+        let members = (0..2usize).map(
+            |_| DictaWav::new(FrontEnd::mfcc(), 64usize, 2usize, 1usize, 128usize, 8usize, true, 0.002f64, 1u64, true, true)
+        ).collect();
+        let mut ensemble = Ensemble::new(members, EnsembleVoting::MajorityVote);
+        ensemble.train("dataset/a/1.wav", String::from("a"));
+        ensemble.train("dataset/a/2.wav", String::from("a"));
+        ensemble.members[1usize].forget("dataset/a/1.wav", "a").unwrap();
+        let trained = ensemble.members[0usize].statistics();
+
+        assert_eq!(ensemble.forget("dataset/a/1.wav", "a"), Err(ForgetError::UntrainedSample(String::from("a"))));
+        assert_eq!(ensemble.members[0usize].statistics(), trained);
+        assert_eq!(ensemble.members[1usize].classes(), vec![(String::from("a"), 1u64)]);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_class_name() {
+        let members = vec![
+            prediction("b", 0.5f64, &[("a", 0.2f64), ("b", 0.4f64)]),
+            prediction("a", 0.5f64, &[("a", 0.4f64), ("b", 0.2f64)]),
+        ];
+
+        for voting in &[EnsembleVoting::MajorityVote, EnsembleVoting::ScoreAveraging, EnsembleVoting::ConfidenceWeighting] {
+            assert_eq!(combine(*voting, members.clone()).class_name, "a");
+            assert_eq!(combine(*voting, members.iter().rev().cloned().collect()).class_name, "a");
+        }
+    }
+}
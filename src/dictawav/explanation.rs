@@ -0,0 +1,124 @@
+use super::wisard::WisardExplanation;
+
+/// Where a retina bit comes from on the painted canvas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanvasBit {
+    pub kernel_index: usize,
+    pub time_segment: usize,
+    pub output_block: usize,
+}
+
+impl CanvasBit {
+    fn from_position(position: usize, kernel_count: usize, time_segments: usize) -> CanvasBit {
+        let region = position % (kernel_count * time_segments);
+
+        CanvasBit {
+            kernel_index: region % kernel_count,
+            time_segment: region / kernel_count,
+            output_block: position / (kernel_count * time_segments),
+        }
+    }
+}
+
+/// A ram that voted for a class and the active canvas bits that formed its address
+#[derive(Clone, Debug, PartialEq)]
+pub struct RamVote {
+    pub ram_index: usize,
+    pub value: u64,
+    pub active_bits: Vec<CanvasBit>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassExplanation {
+    pub class_name: String,
+    pub probability: f64,
+    /// Value of every ram for this utterance, voting or not
+    pub ram_values: Vec<u64>,
+    pub votes: Vec<RamVote>,
+}
+
+/// Why an utterance got its classification
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub classification: String,
+    pub confidence: f64,
+    /// A ram votes when its value is above it, zero unless bleaching had to raise it
+    pub bleaching_threshold: u64,
+    /// Classes from the most to the least probable, so the winner and the runner-up come first
+    pub classes: Vec<ClassExplanation>,
+}
+
+impl Explanation {
+    pub fn new(wisard_explanation: WisardExplanation, retina: &[bool], kernel_count: usize, time_segments: usize) -> Explanation {
+        let ram_active_bits: Vec<Vec<CanvasBit>> = wisard_explanation.ram_retina_positions
+                                                                     .iter()
+                                                                     .map(
+                                                                         |positions|
+                                                                             positions.iter()
+                                                                                      .filter(|&position| retina[*position])
+                                                                                      .map(|position| CanvasBit::from_position(*position, kernel_count, time_segments))
+                                                                                      .collect()
+                                                                     ).collect();
+
+        let classes = wisard_explanation.classes
+                                        .into_iter()
+                                        .map(
+                                            |class_votes| {
+                                                let votes = class_votes.voting_rams
+                                                                       .iter()
+                                                                       .map(
+                                                                           |ram_index|
+                                                                               RamVote {
+                                                                                   ram_index: *ram_index,
+                                                                                   value: class_votes.ram_values[*ram_index],
+                                                                                   active_bits: ram_active_bits[*ram_index].clone(),
+                                                                               }
+                                                                       ).collect();
+                                                ClassExplanation {
+                                                    class_name: class_votes.class_name,
+                                                    probability: class_votes.probability,
+                                                    ram_values: class_votes.ram_values,
+                                                    votes,
+                                                }
+                                            }
+                                        ).collect();
+
+        Explanation {
+            classification: wisard_explanation.classification,
+            confidence: wisard_explanation.confidence,
+            bleaching_threshold: wisard_explanation.bleaching_threshold,
+            classes,
+        }
+    }
+
+    /// Kernels that fed at least one vote of a class, each one once and in increasing order
+    pub fn contributing_kernels(&self, class_name: &str) -> Vec<usize> {
+        let mut kernels: Vec<usize> = self.classes
+                                          .iter()
+                                          .filter(|class| class.class_name == class_name)
+                                          .flat_map(|class| class.votes.iter())
+                                          .flat_map(|vote| vote.active_bits.iter().map(|bit| bit.kernel_index))
+                                          .collect();
+        kernels.sort_unstable();
+        kernels.dedup();
+        kernels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_follow_canvas_layout() {
+        // 4 kernels and 2 time segments per output block
+        assert_eq!(
+            CanvasBit::from_position(13usize, 4usize, 2usize),
+            CanvasBit { kernel_index: 1usize, time_segment: 1usize, output_block: 1usize }
+        );
+        assert_eq!(
+            CanvasBit::from_position(3usize, 4usize, 2usize),
+            CanvasBit { kernel_index: 3usize, time_segment: 0usize, output_block: 0usize }
+        );
+    }
+}
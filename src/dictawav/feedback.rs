@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use super::wisard::ForgetError;
+
+/// How `DictaWav::correct` handles a wrong prediction
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeedbackOptions {
+    /// Forget the file from the predicted class when it was trained with it
+    pub forget_predicted: bool,
+    /// Most corrections kept to be undone, the oldest ones are dropped first
+    pub log_capacity: usize,
+    /// Model file rewritten atomically after every correction and undo
+    pub model_file: Option<PathBuf>,
+}
+
+impl Default for FeedbackOptions {
+    fn default() -> FeedbackOptions {
+        FeedbackOptions {
+            forget_predicted: true,
+            log_capacity: 32usize,
+            model_file: None,
+        }
+    }
+}
+
+/// A wrong prediction and the class the file was trained with instead
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Correction {
+    pub predicted: String,
+    pub actual: String,
+    /// Outcome of forgetting the file from the predicted class, None when the options didn't ask
+    /// for it
+    pub predicted_forgetting: Option<Result<(), ForgetError>>,
+}
+
+impl Correction {
+    /// Whether the file was forgotten from the predicted class
+    pub fn forgot_predicted(&self) -> bool {
+        self.predicted_forgetting == Some(Ok(()))
+    }
+}
+
+/// Why a correction couldn't be applied, undone or persisted
+#[derive(Debug)]
+pub enum FeedbackError {
+    /// The model changed since the correction, which can't be undone anymore
+    Undo(ForgetError),
+    /// The model was updated but couldn't be written to the model file
+    Persist(io::Error),
+}
+
+impl fmt::Display for FeedbackError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FeedbackError::Undo(ref error) => write!(formatter, "DictaWav Error: Correction can't be undone, {}", error),
+            FeedbackError::Persist(ref error) => write!(formatter, "DictaWav Error: Corrected model couldn't be saved, {}!", error),
+        }
+    }
+}
+
+impl Error for FeedbackError {}
+
+/// Latest corrections with the canvases painted for each file and its augmented copies, so undoing
+/// needs no wav file
+#[derive(Default)]
+pub struct CorrectionLog {
+    corrections: VecDeque<(Correction, Vec<Vec<bool>>)>,
+}
+
+impl CorrectionLog {
+    pub fn push(&mut self, correction: Correction, painted_canvases: Vec<Vec<bool>>, capacity: usize) {
+        self.corrections.push_back((correction, painted_canvases));
+        while self.corrections.len() > capacity {
+            self.corrections.pop_front();
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<(Correction, Vec<Vec<bool>>)> {
+        self.corrections.pop_back()
+    }
+
+    /// Puts back a correction that couldn't be undone
+    pub fn restore(&mut self, correction: Correction, painted_canvases: Vec<Vec<bool>>) {
+        self.corrections.push_back((correction, painted_canvases));
+    }
+
+    /// From the oldest to the latest
+    pub fn corrections(&self) -> Vec<Correction> {
+        self.corrections.iter().map(|(correction, _)| correction.clone()).collect()
+    }
+}
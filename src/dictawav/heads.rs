@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use super::wisard::{Prediction, Wisard};
+
+/// Answer of a hierarchical classification, the label only coming from the predicted group
+#[derive(Clone, Debug, PartialEq)]
+pub struct HierarchicalPrediction {
+    pub group: Prediction,
+    /// None when the predicted group has no trained labels, as when no group was decided
+    pub label: Option<Prediction>,
+}
+
+/// Groups classified first, then the labels of the predicted group
+#[derive(Serialize, Deserialize)]
+struct Hierarchy {
+    groups: Wisard,
+    labels: BTreeMap<String, Wisard>,
+}
+
+/// Wisards classifying label groups independently over the same retina
+///
+/// Every head is created the first time it is trained, copying the settings and address mapping of
+/// the Wisard given then.
+#[derive(Serialize, Deserialize)]
+pub struct Heads {
+    heads: BTreeMap<String, Wisard>,
+    hierarchy: Option<Hierarchy>,
+}
+
+impl Heads {
+    pub fn new() -> Heads {
+        Heads {
+            heads: BTreeMap::new(),
+            hierarchy: None,
+        }
+    }
+
+    /// Shares the address mapping of every Wisard with its discriminators again after a load
+    pub fn restore(&mut self) {
+        for head in self.heads.values_mut() {
+            head.restore();
+        }
+        if let Some(ref mut hierarchy) = self.hierarchy {
+            hierarchy.groups.restore();
+            for labels in hierarchy.labels.values_mut() {
+                labels.restore();
+            }
+        }
+    }
+
+    /// Names of the trained heads, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.heads.keys().cloned().collect()
+    }
+
+    /// Trains every head named in `labels` with its label
+    pub fn train(&mut self, retina: &[bool], labels: &[(&str, &str)], template: &Wisard) {
+        for &(head_name, label) in labels {
+            self.heads
+                .entry(String::from(head_name))
+                .or_insert_with(|| template.untrained_copy())
+                .train(String::from(label), retina);
+        }
+    }
+
+    /// Prediction of every head, by head name
+    pub fn predict(&self, retina: &[bool]) -> BTreeMap<String, Prediction> {
+        self.heads
+            .iter()
+            .map(|(head_name, head)| (head_name.clone(), head.predict(retina)))
+            .collect()
+    }
+
+    /// Trains the group classifier with the group and the classifier of the group with the label
+    pub fn train_hierarchical(&mut self, retina: &[bool], group: &str, label: &str, template: &Wisard) {
+        let hierarchy = self.hierarchy.get_or_insert_with(
+            || Hierarchy {
+                groups: template.untrained_copy(),
+                labels: BTreeMap::new(),
+            }
+        );
+
+        hierarchy.groups.train(String::from(group), retina);
+        hierarchy.labels
+                 .entry(String::from(group))
+                 .or_insert_with(|| template.untrained_copy())
+                 .train(String::from(label), retina);
+    }
+
+    /// Classifies the group, then the label among the labels of that group only
+    pub fn predict_hierarchical(&self, retina: &[bool]) -> Option<HierarchicalPrediction> {
+        self.hierarchy.as_ref().map(
+            |hierarchy| {
+                let group = hierarchy.groups.predict(retina);
+                let label = hierarchy.labels.get(&group.class_name).map(|labels| labels.predict(retina));
+                HierarchicalPrediction {
+                    group,
+                    label,
+                }
+            }
+        )
+    }
+
+    /// Heap memory taken by every head and the hierarchy
+    pub fn memory_bytes(&self) -> usize {
+        let hierarchy_bytes = self.hierarchy.as_ref().map_or(0usize, |hierarchy| {
+            hierarchy.groups.memory_bytes()
+                + hierarchy.labels.values().map(|labels| labels.memory_bytes()).sum::<usize>()
+        });
+        self.heads.values().map(|head| head.memory_bytes()).sum::<usize>() + hierarchy_bytes
+    }
+}
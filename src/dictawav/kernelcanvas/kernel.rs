@@ -1,34 +1,40 @@
 extern crate rand;
 
 use self::rand::distributions::{IndependentSample, Range};
-use self::rand::ThreadRng;
+use self::rand::{Rng, SeedableRng, StdRng};
 use std::f64;
+use super::kernel_file::KernelSetError;
+use super::metric::BaseDistance;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Kernel {
     coordinates: Vec<f64>,
 }
 
 impl Kernel {
-    pub fn check_distance_squared(&self, other: &Kernel) -> f64 {
-        let mut distance_squared = 0f64;
-        for (index, coordinate) in self.coordinates.iter().enumerate() {
-            distance_squared += (*coordinate - other.coordinates[index]).powf(2f64)
-        }
+    pub fn coordinates(&self) -> &[f64] {
+        &self.coordinates
+    }
 
-        distance_squared
+    #[inline]
+    pub fn distance_to(&self, point: &[f64], base: BaseDistance) -> f64 {
+        base.between(&self.coordinates, point)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct KernelBuilder {
     dimension: usize,
+    #[serde(skip, default = "KernelBuilder::default_range")]
     range: Range<f64>,
-    random_generator: ThreadRng,
+    #[serde(skip, default = "KernelBuilder::unseeded_generator")]
+    random_generator: StdRng,
 }
 
 impl KernelBuilder {
     pub fn new(dimension: usize) -> KernelBuilder {
-        let range = Range::new(-1f64, 1f64 + f64::MIN_POSITIVE);
-        let random_generator = rand::thread_rng();
+        let range = KernelBuilder::default_range();
+        let random_generator = KernelBuilder::unseeded_generator();
 
         KernelBuilder {
             dimension,
@@ -37,6 +43,20 @@ impl KernelBuilder {
         }
     }
 
+    fn default_range() -> Range<f64> {
+        Range::new(-1f64, 1f64 + f64::MIN_POSITIVE)
+    }
+
+    fn unseeded_generator() -> StdRng {
+        let seed: Vec<usize> = rand::thread_rng().gen_iter().take(4usize).collect();
+        SeedableRng::from_seed(&seed[..])
+    }
+
+    /// Makes the kernels built from now on the same for the same seed
+    pub fn set_seed(&mut self, seed: usize) {
+        self.random_generator = SeedableRng::from_seed(&[seed][..]);
+    }
+
     pub fn build(&mut self) -> Kernel {
         let mut coordinates = Vec::with_capacity(self.dimension);
 
@@ -49,13 +69,21 @@ impl KernelBuilder {
         }
     }
 
-    pub fn build_from_coordinates(&self, coordinates: Vec<f64>) -> Kernel {
-        if self.dimension != coordinates.len() {
-            panic!("KernelBuilder Error: Trying to build a kernel from coordinates with different dimension!");
-        }
-
-        Kernel {
-            coordinates
-        }
+    /// Builds one kernel from each coordinates vector, all of them must have the builder dimension
+    pub fn build_from_coordinates(&self, kernels_coordinates: Vec<Vec<f64>>) -> Result<Vec<Kernel>, KernelSetError> {
+        kernels_coordinates.into_iter()
+                           .enumerate()
+                           .map(
+                               |(kernel_index, coordinates)|
+                                   if coordinates.len() == self.dimension {
+                                       Ok(Kernel { coordinates })
+                                   } else {
+                                       Err(KernelSetError::DimensionMismatch {
+                                           kernel_index,
+                                           expected: self.dimension,
+                                           found: coordinates.len(),
+                                       })
+                                   }
+                           ).collect()
     }
 }
\ No newline at end of file
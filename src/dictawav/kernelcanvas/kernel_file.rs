@@ -0,0 +1,190 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use super::kernel::Kernel;
+
+const BINARY_MAGIC: &[u8; 8] = b"DWKERNEL";
+
+/// File formats of an exported kernel set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelFormat {
+    /// One kernel per line, coordinates separated by commas
+    Csv,
+    /// Magic bytes, then dimension and count as little endian u64, then every coordinate as a
+    /// little endian f64, kernel after kernel
+    Binary,
+}
+
+/// Why a kernel set can't be used by a canvas
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KernelSetError {
+    /// The canvas expects another number of kernels
+    CountMismatch { expected: usize, found: usize },
+    /// A kernel doesn't have the dimension of the processed frames
+    DimensionMismatch { kernel_index: usize, expected: usize, found: usize },
+}
+
+impl fmt::Display for KernelSetError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KernelSetError::CountMismatch { expected, found } => {
+                write!(formatter, "expected {} kernels but got {}", expected, found)
+            }
+            KernelSetError::DimensionMismatch { kernel_index, expected, found } => {
+                write!(formatter, "kernel {} has dimension {} instead of {}", kernel_index, found, expected)
+            }
+        }
+    }
+}
+
+impl Error for KernelSetError {}
+
+pub fn write_kernels<W: Write>(mut writer: W, kernels: &[Kernel], format: KernelFormat) -> io::Result<()> {
+    match format {
+        KernelFormat::Csv => {
+            for kernel in kernels {
+                let line: Vec<String> = kernel.coordinates().iter().map(|value| value.to_string()).collect();
+                writeln!(writer, "{}", line.join(","))?;
+            }
+        }
+        KernelFormat::Binary => {
+            let dimension = kernels.first().map_or(0usize, |kernel| kernel.coordinates().len());
+            writer.write_all(BINARY_MAGIC)?;
+            writer.write_all(&(dimension as u64).to_le_bytes())?;
+            writer.write_all(&(kernels.len() as u64).to_le_bytes())?;
+            for kernel in kernels {
+                for value in kernel.coordinates() {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+/// Reads kernel coordinates, checking that the data is well formed and, before reading any kernel
+/// of a binary file, that its dimension is the one expected
+pub fn read_kernels<R: Read>(reader: R, format: KernelFormat, dimension: usize) -> io::Result<Vec<Vec<f64>>> {
+    match format {
+        KernelFormat::Csv => read_csv(reader),
+        KernelFormat::Binary => read_binary(reader, dimension),
+    }
+}
+
+fn read_csv<R: Read>(reader: R) -> io::Result<Vec<Vec<f64>>> {
+    let mut kernels = Vec::new();
+    for (line_index, line) in io::BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let coordinates = line.split(',')
+                              .map(|value| value.trim().parse::<f64>())
+                              .collect::<Result<Vec<f64>, _>>()
+                              .map_err(|_| invalid_data(format!("invalid coordinate on line {}", line_index + 1usize)))?;
+        kernels.push(coordinates);
+    }
+
+    Ok(kernels)
+}
+
+/// The kernel count of the header isn't trusted, kernels are read until it or the end of the data
+fn read_binary<R: Read>(mut reader: R, expected_dimension: usize) -> io::Result<Vec<Vec<f64>>> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != BINARY_MAGIC {
+        return Err(invalid_data(String::from("not a binary kernel file")));
+    }
+
+    let dimension = read_u64(&mut reader)?;
+    if dimension == 0u64 || dimension != expected_dimension as u64 {
+        return Err(invalid_data(format!("kernel dimension {} instead of {}", dimension, expected_dimension)));
+    }
+    let dimension = expected_dimension;
+    let count = read_u64(&mut reader)?;
+
+    let mut kernels = Vec::with_capacity(count.min(1u64 << 16) as usize);
+    let mut value = [0u8; 8];
+    for _ in 0..count {
+        let mut coordinates = Vec::with_capacity(dimension);
+        for _ in 0..dimension {
+            reader.read_exact(&mut value)?;
+            coordinates.push(f64::from_le_bytes(value));
+        }
+        kernels.push(coordinates);
+    }
+
+    Ok(kernels)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("KernelCanvas Error: {}!", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::kernel::KernelBuilder;
+    use super::super::KernelCanvas;
+
+    #[test]
+    fn formats_round_trip() {
+        let coordinates = vec![vec![0.1f64, -2f64, 1e-300f64], vec![3f64, 0f64, -0.7f64]];
+        let kernels = KernelBuilder::new(3usize).build_from_coordinates(coordinates.clone()).unwrap();
+
+        for format in &[KernelFormat::Csv, KernelFormat::Binary] {
+            let mut buffer = Vec::new();
+            write_kernels(&mut buffer, &kernels, *format).unwrap();
+            assert_eq!(read_kernels(&buffer[..], *format, 3usize).unwrap(), coordinates);
+        }
+
+        assert!(read_kernels(&b"1,2\n3,x\n"[..], KernelFormat::Csv, 2usize).is_err());
+        assert!(read_kernels(&b"DWKERNEL\x01"[..], KernelFormat::Binary, 3usize).is_err());
+    }
+
+    #[test]
+    fn corrupt_binary_headers_are_rejected() {
+        let header = |dimension: u64, count: u64| {
+            let mut bytes = BINARY_MAGIC.to_vec();
+            bytes.extend(&dimension.to_le_bytes());
+            bytes.extend(&count.to_le_bytes());
+            bytes
+        };
+
+        for &dimension in &[0u64, 2u64, u64::MAX] {
+            let error = read_kernels(&header(dimension, u64::MAX)[..], KernelFormat::Binary, 3usize).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+
+        // A count larger than the data stops at its end
+        let mut truncated = header(3u64, u64::MAX);
+        truncated.extend(&1f64.to_le_bytes());
+        let error = read_kernels(&truncated[..], KernelFormat::Binary, 3usize).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn canvas_validates_kernels() {
+        let kernels = vec![vec![0f64; 8usize], vec![1f64; 8usize]];
+        assert!(KernelCanvas::from_kernels(kernels.clone(), 2usize, 1usize, 1usize).is_ok());
+
+        assert_eq!(
+            KernelCanvas::from_kernels(vec![vec![0f64; 8usize], vec![1f64; 7usize]], 2usize, 1usize, 1usize).err(),
+            Some(KernelSetError::DimensionMismatch { kernel_index: 1usize, expected: 8usize, found: 7usize })
+        );
+
+        let mut kernelcanvas = KernelCanvas::new(3usize, 2usize, 1usize, 1usize);
+        assert_eq!(
+            kernelcanvas.set_kernels(kernels),
+            Err(KernelSetError::CountMismatch { expected: 3usize, found: 2usize })
+        );
+    }
+}
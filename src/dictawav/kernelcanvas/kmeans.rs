@@ -0,0 +1,163 @@
+extern crate rand;
+
+use self::rand::distributions::{IndependentSample, Range};
+use self::rand::{Rng, SeedableRng, StdRng};
+use std::f64;
+
+/// How the kernels of a canvas are chosen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelLearning {
+    /// Coordinates drawn uniformly from [-1, 1]
+    Random,
+    /// Lloyd's k-means started from training frames picked at random
+    KMeans { iterations: usize, seed: usize },
+    /// Lloyd's k-means started with the k-means++ seeding
+    KMeansPlusPlus { iterations: usize, seed: usize },
+}
+
+/// Fits `clusters_count` centroids on the given frames
+///
+/// Stops after `iterations` assignment steps or as soon as no frame changes cluster. A cluster left
+/// without frames keeps its previous centroid.
+pub fn fit(
+    frames: &[Vec<f64>],
+    clusters_count: usize,
+    iterations: usize,
+    seed: usize,
+    plus_plus: bool,
+) -> Vec<Vec<f64>> {
+    if frames.len() < clusters_count {
+        panic!("KernelCanvas Error: Trying to learn more kernels than available training frames!");
+    }
+
+    let mut random_generator: StdRng = SeedableRng::from_seed(&[seed][..]);
+    let mut centroids = if plus_plus {
+        plus_plus_seeding(frames, clusters_count, &mut random_generator)
+    } else {
+        rand::seq::sample_iter(&mut random_generator, frames.iter(), clusters_count)
+            .unwrap()
+            .into_iter()
+            .cloned()
+            .collect()
+    };
+
+    let dimension = frames[0].len();
+    let mut assignments = vec![usize::MAX; frames.len()];
+
+    for _ in 0..iterations {
+        let mut changed = false;
+        for (frame, assignment) in frames.iter().zip(assignments.iter_mut()) {
+            let (nearest, _) = nearest_centroid(frame, &centroids);
+            if nearest != *assignment {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+
+        let mut sums = vec![vec![0f64; dimension]; clusters_count];
+        let mut counts = vec![0usize; clusters_count];
+        for (frame, assignment) in frames.iter().zip(assignments.iter()) {
+            counts[*assignment] += 1usize;
+            for (sum, value) in sums[*assignment].iter_mut().zip(frame.iter()) {
+                *sum += *value;
+            }
+        }
+
+        for (centroid, (sum, count)) in centroids.iter_mut().zip(sums.into_iter().zip(counts)) {
+            if count > 0usize {
+                *centroid = sum.into_iter().map(|value| value / count as f64).collect();
+            }
+        }
+    }
+
+    centroids
+}
+
+/// Picks each new centroid with probability proportional to its squared distance to the nearest one
+fn plus_plus_seeding(frames: &[Vec<f64>], clusters_count: usize, random_generator: &mut StdRng) -> Vec<Vec<f64>> {
+    let mut centroids = Vec::with_capacity(clusters_count);
+    centroids.push(frames[random_generator.gen_range(0usize, frames.len())].clone());
+
+    let mut distances: Vec<f64> = frames.iter().map(|frame| distance_squared(frame, &centroids[0])).collect();
+
+    while centroids.len() < clusters_count {
+        let total: f64 = distances.iter().sum();
+
+        let chosen = if total > 0f64 {
+            let target = Range::new(0f64, total).ind_sample(random_generator);
+            let mut accumulated = 0f64;
+            let mut chosen = frames.len() - 1usize;
+            for (index, distance) in distances.iter().enumerate() {
+                accumulated += *distance;
+                if accumulated > target {
+                    chosen = index;
+                    break;
+                }
+            }
+            chosen
+        } else {
+            // Every frame already lies on a centroid
+            random_generator.gen_range(0usize, frames.len())
+        };
+
+        centroids.push(frames[chosen].clone());
+        let newest = &centroids[centroids.len() - 1usize];
+        for (distance, frame) in distances.iter_mut().zip(frames.iter()) {
+            *distance = distance.min(distance_squared(frame, newest));
+        }
+    }
+
+    centroids
+}
+
+fn nearest_centroid(frame: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+    let mut nearest_index = 0usize;
+    let mut nearest_distance = f64::MAX;
+
+    for (index, centroid) in centroids.iter().enumerate() {
+        let distance = distance_squared(frame, centroid);
+        if distance < nearest_distance {
+            nearest_distance = distance;
+            nearest_index = index;
+        }
+    }
+
+    (nearest_index, nearest_distance)
+}
+
+#[inline]
+fn distance_squared(first: &[f64], second: &[f64]) -> f64 {
+    first.iter().zip(second.iter()).map(|(a, b)| (*a - *b) * (*a - *b)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_separated_clusters() {
+        let frames = vec![
+            vec![0f64, 0f64], vec![0.1f64, 0f64], vec![0f64, 0.1f64],
+            vec![5f64, 5f64], vec![5.1f64, 5f64], vec![5f64, 5.1f64],
+        ];
+
+        for plus_plus in &[false, true] {
+            let mut centroids = fit(&frames, 2usize, 20usize, 7usize, *plus_plus);
+            centroids.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
+
+            assert!(distance_squared(&centroids[0], &[0.1f64 / 3f64, 0.1f64 / 3f64]) < 1e-12);
+            assert!(distance_squared(&centroids[1], &[5f64 + 0.1f64 / 3f64, 5f64 + 0.1f64 / 3f64]) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_centroids() {
+        let frames: Vec<Vec<f64>> = (0..50).map(|index| vec![(index * 7 % 13) as f64, (index % 5) as f64]).collect();
+
+        assert_eq!(fit(&frames, 4usize, 5usize, 42usize, true), fit(&frames, 4usize, 5usize, 42usize, true));
+    }
+}
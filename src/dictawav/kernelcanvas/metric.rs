@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::f64;
+
+/// Distance between frames and kernels used to paint the canvas
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    Euclidean,
+    Manhattan,
+    /// One minus the cosine similarity
+    Cosine,
+    Chebyshev,
+    /// Euclidean distance after whitening with the covariance of training frames
+    ///
+    /// Random kernels end up far from the whitened frames, so it works best with learned kernels
+    Mahalanobis,
+}
+
+/// Distance actually computed between transformed vectors
+///
+/// Cosine and Mahalanobis distances are squared euclidean distances between normalised or whitened
+/// vectors, so every metric can be searched with the same exact structures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseDistance {
+    SquaredEuclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl BaseDistance {
+    /// Adds the difference along one more dimension to a partial distance
+    #[inline]
+    pub fn accumulate(self, partial_distance: f64, difference: f64) -> f64 {
+        match self {
+            BaseDistance::SquaredEuclidean => partial_distance + difference * difference,
+            BaseDistance::Manhattan => partial_distance + difference.abs(),
+            BaseDistance::Chebyshev => partial_distance.max(difference.abs()),
+        }
+    }
+
+    #[inline]
+    pub fn between(self, first: &[f64], second: &[f64]) -> f64 {
+        let mut distance = 0f64;
+        for (a, b) in first.iter().zip(second.iter()) {
+            distance = self.accumulate(distance, *a - *b);
+        }
+
+        distance
+    }
+
+    /// Lower bound of the distance between points whose coordinates differ by `difference` on one axis
+    #[inline]
+    pub fn axis_bound(self, difference: f64) -> f64 {
+        self.accumulate(0f64, difference)
+    }
+}
+
+impl DistanceMetric {
+    pub fn base(self) -> BaseDistance {
+        match self {
+            DistanceMetric::Euclidean | DistanceMetric::Cosine | DistanceMetric::Mahalanobis => BaseDistance::SquaredEuclidean,
+            DistanceMetric::Manhattan => BaseDistance::Manhattan,
+            DistanceMetric::Chebyshev => BaseDistance::Chebyshev,
+        }
+    }
+
+    /// Converts a base distance to the distance of this metric
+    #[inline]
+    pub fn to_distance(self, base_distance: f64) -> f64 {
+        match self {
+            DistanceMetric::Euclidean | DistanceMetric::Mahalanobis => base_distance.sqrt(),
+            // Between unit vectors the squared euclidean distance is twice one minus the cosine
+            DistanceMetric::Cosine => base_distance / 2f64,
+            DistanceMetric::Manhattan | DistanceMetric::Chebyshev => base_distance,
+        }
+    }
+
+    /// Converts a distance of this metric to a base distance
+    #[inline]
+    pub fn from_distance(self, distance: f64) -> f64 {
+        match self {
+            DistanceMetric::Euclidean | DistanceMetric::Mahalanobis => distance * distance,
+            DistanceMetric::Cosine => distance * 2f64,
+            DistanceMetric::Manhattan | DistanceMetric::Chebyshev => distance,
+        }
+    }
+
+    /// Maps a frame or kernel to the space where the base distance is computed, borrowing it when
+    /// the metric leaves it as it is
+    pub fn transform<'a>(self, point: &'a [f64], whitening: Option<&Whitening>) -> Cow<'a, [f64]> {
+        match self {
+            DistanceMetric::Cosine => {
+                let norm = point.iter().map(|value| value * value).sum::<f64>().sqrt();
+                if norm > 0f64 {
+                    Cow::Owned(point.iter().map(|value| value / norm).collect())
+                } else {
+                    Cow::Borrowed(point)
+                }
+            }
+            DistanceMetric::Mahalanobis => {
+                match whitening {
+                    Some(whitening) => Cow::Owned(whitening.apply(point)),
+                    None => panic!("KernelCanvas Error: Mahalanobis distance requires the covariance of training frames!"),
+                }
+            }
+            _ => Cow::Borrowed(point),
+        }
+    }
+}
+
+/// Whitening by the inverse of the Cholesky factor of a covariance matrix
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Whitening {
+    cholesky_factor: Vec<Vec<f64>>,
+}
+
+impl Whitening {
+    /// Learns the covariance of the given frames, with a small ridge keeping it invertible
+    pub fn from_frames(frames: &[Vec<f64>]) -> Whitening {
+        if frames.len() < 2usize {
+            panic!("KernelCanvas Error: Trying to learn a covariance with less than two frames!");
+        }
+
+        let dimension = frames[0].len();
+        let count = frames.len() as f64;
+        let mut means = vec![0f64; dimension];
+        for frame in frames {
+            for (mean, value) in means.iter_mut().zip(frame.iter()) {
+                *mean += *value / count;
+            }
+        }
+
+        let mut covariance = vec![vec![0f64; dimension]; dimension];
+        for frame in frames {
+            for row in 0..dimension {
+                let row_difference = frame[row] - means[row];
+                for column in 0..(row + 1usize) {
+                    covariance[row][column] += row_difference * (frame[column] - means[column]) / (count - 1f64);
+                }
+            }
+        }
+
+        let trace: f64 = (0..dimension).map(|index| covariance[index][index]).sum();
+        let ridge = 1e-6 * trace / dimension as f64 + 1e-12;
+        for (index, row) in covariance.iter_mut().enumerate() {
+            row[index] += ridge;
+        }
+
+        Whitening {
+            cholesky_factor: Whitening::cholesky(&covariance, ridge),
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.cholesky_factor.len()
+    }
+
+    /// Lower triangular factor of a symmetric matrix, only reading its lower half
+    fn cholesky(matrix: &[Vec<f64>], minimum_pivot: f64) -> Vec<Vec<f64>> {
+        let dimension = matrix.len();
+        let mut factor = vec![vec![0f64; dimension]; dimension];
+
+        for row in 0..dimension {
+            for column in 0..(row + 1usize) {
+                let sum = matrix[row][column] - factor[row][..column].iter()
+                                                                  .zip(factor[column][..column].iter())
+                                                                  .map(|(first, second)| first * second)
+                                                                  .sum::<f64>();
+
+                if row == column {
+                    factor[row][column] = sum.max(minimum_pivot).sqrt();
+                } else {
+                    factor[row][column] = sum / factor[column][column];
+                }
+            }
+        }
+
+        factor
+    }
+
+    /// Solves `L y = point` by forward substitution
+    pub fn apply(&self, point: &[f64]) -> Vec<f64> {
+        let mut whitened = Vec::with_capacity(point.len());
+        for (row, factor_row) in self.cholesky_factor.iter().enumerate() {
+            let mut value = point[row];
+            for (column, whitened_value) in whitened.iter().enumerate() {
+                value -= factor_row[column] * whitened_value;
+            }
+            whitened.push(value / factor_row[row]);
+        }
+
+        whitened
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_distances() {
+        let first = [1f64, -2f64, 3f64];
+        let second = [-1f64, 2f64, 3f64];
+
+        assert_eq!(BaseDistance::SquaredEuclidean.between(&first, &second), 20f64);
+        assert_eq!(BaseDistance::Manhattan.between(&first, &second), 6f64);
+        assert_eq!(BaseDistance::Chebyshev.between(&first, &second), 4f64);
+
+        let cosine = DistanceMetric::Cosine;
+        let orthogonal = BaseDistance::SquaredEuclidean.between(
+            &cosine.transform(&[2f64, 0f64], None),
+            &cosine.transform(&[0f64, 3f64], None),
+        );
+        assert!((cosine.to_distance(orthogonal) - 1f64).abs() < 1e-12);
+
+        // Frames aren't copied for metrics computing distances between them as they are
+        for metric in &[DistanceMetric::Euclidean, DistanceMetric::Manhattan, DistanceMetric::Chebyshev] {
+            assert!(matches!(metric.transform(&first, None), Cow::Borrowed(_)));
+        }
+    }
+
+    #[test]
+    fn whitening_matches_mahalanobis_distance() {
+        // Independent dimensions with standard deviations 2 and 1
+        let frames = vec![
+            vec![2f64, 1f64], vec![-2f64, -1f64], vec![2f64, -1f64], vec![-2f64, 1f64],
+        ];
+        let whitening = Whitening::from_frames(&frames);
+        let variances = [16f64 / 3f64, 4f64 / 3f64];
+
+        let first = whitening.apply(&[4f64, 0f64]);
+        let second = whitening.apply(&[0f64, 2f64]);
+        let expected = 16f64 / variances[0] + 4f64 / variances[1];
+
+        assert_eq!(whitening.dimension(), 2usize);
+        assert!((BaseDistance::SquaredEuclidean.between(&first, &second) - expected).abs() < 1e-4);
+    }
+}
@@ -1,39 +1,158 @@
 mod kernel;
-
-use std::f64;
+mod kernel_file;
+mod kmeans;
+mod metric;
+mod nearest;
+mod normalization;
+mod painting;
+
+use std::collections::hash_map::HashMap;
+use std::io::{self, Read, Write};
 use self::kernel::Kernel;
 use self::kernel::KernelBuilder;
+pub use self::kernel_file::{KernelFormat, KernelSetError};
+pub use self::kmeans::KernelLearning;
+pub use self::metric::{DistanceMetric, Whitening};
+use self::nearest::KernelIndex;
+pub use self::nearest::NearestKernelSearch;
+pub use self::normalization::{CmvnAccumulator, CmvnStatistics, Nonlinearity, Normalization};
+pub use self::painting::{CanvasEncoding, PaintingMode};
+use std::f64;
 
+#[derive(Serialize, Deserialize)]
 pub struct KernelCanvas {
     kernel_count: usize,
     kernel_dimension: usize,
     output_factor: usize,
+    time_segments: usize,
     kernel_builder: KernelBuilder,
     kernels: Vec<Kernel>,
-    active_kernels: Vec<bool>,
+    /// Whether the kernels were drawn by the kernel builder rather than learned or imported
+    random_kernels: bool,
+    distance_metric: DistanceMetric,
+    whitening: Option<Whitening>,
+    /// Kernels in the space where the base distance of the metric is computed
+    #[serde(skip)]
+    metric_kernels: Vec<Kernel>,
+    nearest_kernel_search: NearestKernelSearch,
+    #[serde(skip)]
+    kernel_index: KernelIndex,
+    painting_mode: PaintingMode,
+    encoding: CanvasEncoding,
+    #[serde(skip)]
+    kernel_hits: Vec<u64>,
+    #[serde(skip)]
+    nearest_distances: Vec<f64>,
+    distance_thresholds: Option<Vec<Vec<f64>>>,
+    #[serde(skip)]
     processed_frames: Vec<Vec<f64>>,
+    kernel_activations: Vec<u64>,
+    normalization: Normalization,
+    nonlinearity: Nonlinearity,
+    global_statistics: Option<CmvnStatistics>,
+    speaker_statistics: HashMap<String, CmvnStatistics>,
+    current_speaker: Option<String>,
 }
 
 impl KernelCanvas {
-    pub fn new(kernel_count: usize, kernel_dimension: usize, output_factor: usize) -> KernelCanvas {
+    /// Creates a canvas whose retina has `output_factor` blocks, each one split in `time_segments`
+    /// regions of one bit per kernel
+    pub fn new(kernel_count: usize, kernel_dimension: usize, output_factor: usize, time_segments: usize) -> KernelCanvas {
         let mut kernel_builder = KernelBuilder::new(kernel_dimension * 4usize);
 
         let mut kernels = Vec::with_capacity(kernel_count);
         for _ in 0..kernel_count {
             kernels.push(kernel_builder.build());
         }
-        let active_kernels = Vec::new();
+
+        KernelCanvas::with_kernels(kernel_builder, kernels, kernel_dimension, output_factor, time_segments)
+    }
+
+    fn with_kernels(
+        kernel_builder: KernelBuilder,
+        kernels: Vec<Kernel>,
+        kernel_dimension: usize,
+        output_factor: usize,
+        time_segments: usize,
+    ) -> KernelCanvas {
+        if time_segments == 0usize {
+            panic!("KernelCanvas Error: A canvas needs at least one time segment!");
+        }
+        let kernel_count = kernels.len();
+        let kernel_hits = vec![0u64; kernel_count * time_segments];
         let processed_frames = Vec::new();
 
-        KernelCanvas {
+        let mut kernelcanvas = KernelCanvas {
             kernel_count,
             kernel_dimension,
             output_factor,
+            time_segments,
             kernel_builder,
             kernels,
-            active_kernels,
+            random_kernels: true,
+            distance_metric: DistanceMetric::Euclidean,
+            whitening: None,
+            metric_kernels: Vec::new(),
+            nearest_kernel_search: NearestKernelSearch::FlatScan,
+            kernel_index: KernelIndex::default(),
+            painting_mode: PaintingMode::Nearest,
+            encoding: CanvasEncoding::Repetition,
+            kernel_hits,
+            nearest_distances: Vec::new(),
+            distance_thresholds: None,
             processed_frames,
+            kernel_activations: vec![0u64; kernel_count],
+            normalization: Normalization::PerUtterance,
+            nonlinearity: Nonlinearity::Tanh,
+            global_statistics: None,
+            speaker_statistics: HashMap::new(),
+            current_speaker: None,
+        };
+        kernelcanvas.rebuild_kernel_index();
+
+        kernelcanvas
+    }
+
+    /// Rebuilds what isn't saved with a model, to be called after loading one
+    pub fn restore(&mut self) {
+        self.kernel_hits = vec![0u64; self.kernel_count * self.time_segments];
+        self.rebuild_kernel_index();
+    }
+
+    pub fn get_distance_metric(&self) -> DistanceMetric {
+        self.distance_metric
+    }
+
+    /// Selects the distance between frames and kernels, Mahalanobis requires a fitted covariance
+    pub fn set_distance_metric(&mut self, distance_metric: DistanceMetric) {
+        if distance_metric == DistanceMetric::Mahalanobis && self.whitening.is_none() {
+            panic!("KernelCanvas Error: Mahalanobis distance requires the covariance of training frames!");
         }
+        self.distance_metric = distance_metric;
+        self.rebuild_kernel_index();
+    }
+
+    /// Learns the covariance used by the Mahalanobis distance from processed frames
+    pub fn fit_covariance(&mut self, frames: &[Vec<f64>]) {
+        let whitening = Whitening::from_frames(frames);
+        if whitening.dimension() != self.kernel_dimension * 4usize {
+            panic!("KernelCanvas Error: Trying to learn a covariance of frames with different dimension!");
+        }
+        self.whitening = Some(whitening);
+        self.rebuild_kernel_index();
+    }
+
+    fn rebuild_kernel_index(&mut self) {
+        let distance_metric = self.distance_metric;
+        let whitening = self.whitening.as_ref();
+        let metric_coordinates = self.kernels
+                                     .iter()
+                                     .map(|kernel| distance_metric.transform(kernel.coordinates(), whitening).into_owned())
+                                     .collect();
+        self.metric_kernels = self.kernel_builder
+                                  .build_from_coordinates(metric_coordinates)
+                                  .unwrap_or_else(|error| panic!("KernelCanvas Error: {}!", error));
+        self.kernel_index = KernelIndex::new(self.nearest_kernel_search, &self.metric_kernels, distance_metric.base());
     }
 
     pub fn process(&mut self, frames: Vec<Vec<f64>>) {
@@ -42,19 +161,228 @@ impl KernelCanvas {
         self.processed_frames = Vec::new();
 
         self.append_sum(frames);
-        self.zscore_and_tanh();
+        self.normalize();
         self.replicate_features();
     }
 
+    /// Frames of the last processed utterance, as they are compared with the kernels
+    pub fn get_processed_frames(&self) -> &[Vec<f64>] {
+        &self.processed_frames
+    }
+
+    /// Draws new random kernels from a seed, later random kernels following from it as well
+    ///
+    /// Learned or imported kernels would be lost, so it panics once the kernels were set.
+    pub fn set_seed(&mut self, seed: usize) {
+        if !self.random_kernels {
+            panic!("KernelCanvas Error: Seeding would replace kernels that were learned or imported!");
+        }
+        self.kernel_builder.set_seed(seed);
+        self.learn_kernels(&[], KernelLearning::Random);
+    }
+
+    /// Replaces the kernels, fitting them on the given processed frames unless they are random
+    pub fn learn_kernels(&mut self, frames: &[Vec<f64>], learning: KernelLearning) {
+        let centroids = match learning {
+            KernelLearning::Random => {
+                let mut kernels = Vec::with_capacity(self.kernel_count);
+                for _ in 0..self.kernel_count {
+                    kernels.push(self.kernel_builder.build());
+                }
+                self.replace_kernels(kernels);
+                self.random_kernels = true;
+                return;
+            }
+            KernelLearning::KMeans { iterations, seed } => {
+                kmeans::fit(frames, self.kernel_count, iterations, seed, false)
+            }
+            KernelLearning::KMeansPlusPlus { iterations, seed } => {
+                kmeans::fit(frames, self.kernel_count, iterations, seed, true)
+            }
+        };
+
+        self.set_kernels(centroids).unwrap_or_else(|error| panic!("KernelCanvas Error: {}!", error));
+    }
+
+    /// Creates a canvas painted with the given kernels instead of random ones
+    ///
+    /// Each kernel must have the dimension of the processed frames, four times `feature_dimension`.
+    pub fn from_kernels(
+        kernels: Vec<Vec<f64>>,
+        feature_dimension: usize,
+        output_factor: usize,
+        time_segments: usize,
+    ) -> Result<KernelCanvas, KernelSetError> {
+        let kernel_builder = KernelBuilder::new(feature_dimension * 4usize);
+        let kernels = kernel_builder.build_from_coordinates(kernels)?;
+        let mut kernelcanvas = KernelCanvas::with_kernels(kernel_builder, kernels, feature_dimension, output_factor, time_segments);
+        kernelcanvas.random_kernels = false;
+        Ok(kernelcanvas)
+    }
+
+    /// Replaces the kernels keeping their count, kernel activation counts and distance thresholds
+    /// are reset
+    pub fn set_kernels(&mut self, kernels: Vec<Vec<f64>>) -> Result<(), KernelSetError> {
+        if kernels.len() != self.kernel_count {
+            return Err(KernelSetError::CountMismatch { expected: self.kernel_count, found: kernels.len() });
+        }
+
+        let kernels = self.kernel_builder.build_from_coordinates(kernels)?;
+        self.replace_kernels(kernels);
+        self.random_kernels = false;
+        Ok(())
+    }
+
+    /// Thresholds learned for the previous kernels don't apply to the new ones
+    fn replace_kernels(&mut self, kernels: Vec<Kernel>) {
+        self.kernels = kernels;
+        self.distance_thresholds = None;
+        self.rebuild_kernel_index();
+        self.reset_kernel_activations();
+    }
+
+    pub fn export_kernels<W: Write>(&self, writer: W, format: KernelFormat) -> io::Result<()> {
+        kernel_file::write_kernels(writer, &self.kernels, format)
+    }
+
+    /// Replaces the kernels with the ones read from an exported kernel set
+    pub fn import_kernels<R: Read>(&mut self, reader: R, format: KernelFormat) -> io::Result<()> {
+        let kernels = kernel_file::read_kernels(reader, format, self.kernel_dimension * 4usize)?;
+        self.set_kernels(kernels).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn set_painting_mode(&mut self, painting_mode: PaintingMode) {
+        match painting_mode {
+            PaintingMode::KNearest { k } if k == 0usize || k > self.kernel_count => {
+                panic!("KernelCanvas Error: Invalid number of nearest kernels to activate!");
+            }
+            PaintingMode::Radius { radius } if radius.is_nan() || radius < 0f64 => {
+                panic!("KernelCanvas Error: Invalid activation radius!");
+            }
+            _ => self.painting_mode = painting_mode,
+        }
+    }
+
+    pub fn set_encoding(&mut self, encoding: CanvasEncoding) {
+        if encoding == (CanvasEncoding::HitThermometer { step: 0u64 }) {
+            panic!("KernelCanvas Error: Hit thermometer step must be at least one!");
+        }
+        self.encoding = encoding;
+    }
+
+    /// Learns the thresholds of `CanvasEncoding::DistanceThresholds` from the nearest distances of
+    /// the kernels over training utterances, as given by `utterance_nearest_distances`
+    ///
+    /// Replacing the kernels afterwards drops the thresholds, which must be learned again.
+    pub fn fit_distance_thresholds(&mut self, nearest_distances: &[Vec<f64>]) {
+        self.distance_thresholds = Some(
+            painting::distance_quantiles(nearest_distances, self.kernel_count, self.output_factor)
+        );
+    }
+
+    /// Distance from each kernel to the nearest frame of the last processed utterance
+    pub fn utterance_nearest_distances(&self) -> Vec<f64> {
+        let mut nearest_distances = vec![f64::INFINITY; self.kernel_count];
+        for frame in &self.processed_frames {
+            let frame = self.distance_metric.transform(frame, self.whitening.as_ref());
+            for (nearest, distance) in nearest_distances.iter_mut().zip(self.kernel_index.distances(&self.metric_kernels, &frame)) {
+                *nearest = nearest.min(distance);
+            }
+        }
+
+        nearest_distances.into_iter().map(|distance| self.distance_metric.to_distance(distance)).collect()
+    }
+
+    /// Selects the nearest kernel search structure, all of them give the same canvas
+    pub fn set_nearest_kernel_search(&mut self, search: NearestKernelSearch) {
+        self.nearest_kernel_search = search;
+        self.rebuild_kernel_index();
+    }
+
+    pub fn get_kernel_count(&self) -> usize {
+        self.kernel_count
+    }
+
+    pub fn get_time_segments(&self) -> usize {
+        self.time_segments
+    }
+
+    pub fn get_output_factor(&self) -> usize {
+        self.output_factor
+    }
+
+    /// How many kernels were hit by at least one counted frame since the last reset, as its
+    /// nearest kernel or one of those the painting mode also activates
+    pub fn activated_kernels_count(&self) -> usize {
+        self.kernel_activations.iter().filter(|&count| *count > 0u64).count()
+    }
+
+    pub fn reset_kernel_activations(&mut self) {
+        self.kernel_activations = vec![0u64; self.kernel_count];
+    }
+
+    pub fn set_normalization(&mut self, normalization: Normalization) {
+        self.normalization = normalization;
+    }
+
+    pub fn set_nonlinearity(&mut self, nonlinearity: Nonlinearity) {
+        self.nonlinearity = nonlinearity;
+    }
+
+    /// Selects whose statistics are used by `Normalization::PerSpeaker`
+    pub fn set_current_speaker(&mut self, speaker: Option<String>) {
+        self.current_speaker = speaker;
+    }
+
+    /// Adds the frames of an utterance to an accumulator, using the same features that are normalised
+    pub fn accumulate_statistics(&mut self, frames: Vec<Vec<f64>>, accumulator: &mut CmvnAccumulator) {
+        self.processed_frames = Vec::new();
+        self.append_sum(frames);
+        accumulator.add_frames(&self.processed_frames);
+        self.processed_frames = Vec::new();
+    }
+
+    pub fn set_global_statistics(&mut self, statistics: CmvnStatistics) {
+        self.check_statistics_dimension(&statistics);
+        self.global_statistics = Some(statistics);
+    }
+
+    pub fn set_speaker_statistics(&mut self, speaker: String, statistics: CmvnStatistics) {
+        self.check_statistics_dimension(&statistics);
+        self.speaker_statistics.insert(speaker, statistics);
+    }
+
+    /// Number of bits of every painted canvas
+    pub fn retina_size(&self) -> usize {
+        self.kernel_count * self.time_segments * self.output_factor
+    }
+
     pub fn get_painted_canvas(&mut self) -> Vec<bool> {
-        self.paint_canvas();
+        self.painted_canvas(false)
+    }
 
-        let mut painted_canvas = Vec::with_capacity(self.kernel_count * self.output_factor);
+    /// Paints the canvas like `get_painted_canvas`, counting the kernel hits as activations
+    pub fn get_training_canvas(&mut self) -> Vec<bool> {
+        self.painted_canvas(true)
+    }
 
-        for _ in 0..self.output_factor {
-            painted_canvas.extend(self.active_kernels.iter());
+    fn painted_canvas(&mut self, counts_activations: bool) -> Vec<bool> {
+        self.paint_canvas();
+        if counts_activations {
+            for segment_hits in self.kernel_hits.chunks(self.kernel_count) {
+                for (activations, hits) in self.kernel_activations.iter_mut().zip(segment_hits.iter()) {
+                    *activations += *hits;
+                }
+            }
         }
 
+        let painted_canvas = self.encoding.encode(
+            &self.kernel_hits,
+            &self.nearest_distances,
+            self.distance_thresholds.as_deref(),
+            self.output_factor,
+        );
+
         self.clean_canvas();
         painted_canvas
     }
@@ -80,47 +408,37 @@ impl KernelCanvas {
         }
     }
 
-    fn zscore_and_tanh(&mut self) {
-        let processed_frames_count = self.processed_frames.len();
-        let mut processed = Vec::with_capacity(processed_frames_count);
-        let doubled_kernel_dimension = self.kernel_dimension * 2usize;
-
-        let mut means = vec![0f64; doubled_kernel_dimension];
-        let mut std_deviations = vec![0f64; doubled_kernel_dimension];
-
-        for frame in &self.processed_frames {
-            for (index, num) in means.iter_mut().enumerate() {
-                *num += frame[index];
+    fn normalize(&mut self) {
+        let processed = match self.normalization {
+            Normalization::PerUtterance => {
+                CmvnStatistics::from_frames(&self.processed_frames).normalize_frames(&self.processed_frames, self.nonlinearity)
             }
-        }
-
-        for mean in &mut means {
-            *mean /= processed_frames_count as f64; // Calculating the mean of each dimension
-        }
-
-        for frame in &self.processed_frames {
-            for (index, num) in std_deviations.iter_mut().enumerate() {
-                *num += (frame[index] - means[index]).powf(2f64);
+            Normalization::Global => {
+                match self.global_statistics {
+                    Some(ref statistics) => statistics.normalize_frames(&self.processed_frames, self.nonlinearity),
+                    None => panic!("KernelCanvas Error: Global normalization requires statistics learned over the training set!"),
+                }
             }
-        }
-
-        for std_deviation in &mut std_deviations {
-            *std_deviation /= (processed_frames_count - 1usize) as f64;
-        }
-
-        for frame in &self.processed_frames {
-            let mut zscored_frame = Vec::with_capacity(doubled_kernel_dimension);
-            for index in 0..doubled_kernel_dimension {
-                zscored_frame.push(
-                    ((frame[index] - means[index]) / std_deviations[index]).tanh()
-                );
+            Normalization::PerSpeaker => {
+                let statistics = self.current_speaker
+                                     .as_ref()
+                                     .and_then(|speaker| self.speaker_statistics.get(speaker));
+                match statistics {
+                    Some(statistics) => statistics.normalize_frames(&self.processed_frames, self.nonlinearity),
+                    None => panic!("KernelCanvas Error: Per speaker normalization requires statistics of the current speaker!"),
+                }
             }
-            processed.push(zscored_frame);
-        }
+        };
 
         self.processed_frames = processed;
     }
 
+    fn check_statistics_dimension(&self, statistics: &CmvnStatistics) {
+        if statistics.dimension() != self.kernel_dimension * 2usize {
+            panic!("KernelCanvas Error: Trying to use statistics with different dimension!");
+        }
+    }
+
     fn replicate_features(&mut self) {
         let doubled_kernel_dimension = self.kernel_dimension * 2usize;
 
@@ -131,39 +449,141 @@ impl KernelCanvas {
 
         for index in 1..self.processed_frames.len() {
             let previous_frame: Vec<f64> = self.processed_frames[index - 1].iter().take(doubled_kernel_dimension).cloned().collect();
-            self.processed_frames[index].extend(previous_frame.into_iter());
+            self.processed_frames[index].extend(previous_frame);
         }
     }
 
-    fn get_nearest_kernel_index(&self, frame: Vec<f64>) -> usize {
-        let current_kernel = self.kernel_builder.build_from_coordinates(frame);
-        let mut nearest_kernel_index = 0usize;
-        let mut nearest_kernel_distance = f64::MAX;
+    /// Counts the hits of each kernel, frames of each time segment hitting their own region
+    fn paint_canvas(&mut self) {
+        let frames_count = self.processed_frames.len();
+        let needs_distances = self.encoding.needs_distances();
+        let mut kernel_hits = vec![0u64; self.kernel_count * self.time_segments];
+        let mut nearest_distances = if needs_distances {
+            vec![f64::INFINITY; self.kernel_count * self.time_segments]
+        } else {
+            Vec::new()
+        };
+
+        for (frame_index, frame) in self.processed_frames.iter().enumerate() {
+            let frame: &[f64] = &self.distance_metric.transform(frame, self.whitening.as_ref());
+            let segment = frame_index * self.time_segments / frames_count;
+            let segment_range = segment * self.kernel_count..(segment + 1usize) * self.kernel_count;
+
+            if needs_distances {
+                let distances = self.kernel_index.distances(&self.metric_kernels, frame);
+                for (nearest, distance) in nearest_distances[segment_range.clone()].iter_mut().zip(distances) {
+                    *nearest = nearest.min(distance);
+                }
+            }
 
-        for index in 0..self.kernel_count {
-            let distance = self.kernels[index].check_distance_squared(&current_kernel);
-            if distance < nearest_kernel_distance {
-                nearest_kernel_distance = distance;
-                nearest_kernel_index = index;
+            let segment_hits = &mut kernel_hits[segment_range];
+
+            match self.painting_mode {
+                PaintingMode::Nearest => {
+                    segment_hits[self.kernel_index.nearest(&self.metric_kernels, frame)] += 1u64;
+                }
+                PaintingMode::KNearest { k } => {
+                    for index in self.kernel_index.k_nearest(&self.metric_kernels, frame, k) {
+                        segment_hits[index] += 1u64;
+                    }
+                }
+                PaintingMode::Radius { radius } => {
+                    let nearest_kernel_index = self.kernel_index.nearest(&self.metric_kernels, frame);
+                    segment_hits[nearest_kernel_index] += 1u64;
+                    for index in self.kernel_index.within_radius(&self.metric_kernels, frame, self.distance_metric.from_distance(radius)) {
+                        if index != nearest_kernel_index {
+                            segment_hits[index] += 1u64;
+                        }
+                    }
+                }
             }
         }
 
-        nearest_kernel_index
+        self.kernel_hits = kernel_hits;
+        self.nearest_distances = nearest_distances.into_iter().map(|distance| self.distance_metric.to_distance(distance)).collect();
     }
 
-    fn paint_canvas(&mut self) {
-        let mut active_kernels = vec![false; self.kernel_count];
-        for frame in &self.processed_frames {
-            active_kernels[self.get_nearest_kernel_index(frame.clone())] = true;
+    fn clean_canvas(&mut self) {
+        for hits in &mut self.kernel_hits {
+            *hits = 0u64;
         }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        self.active_kernels = active_kernels;
+    /// Canvas of two kernels, one at the origin and one at (1, 1, 1, 1)
+    fn two_kernel_canvas(output_factor: usize, time_segments: usize) -> KernelCanvas {
+        let mut kernelcanvas = KernelCanvas::new(2usize, 1usize, output_factor, time_segments);
+        kernelcanvas.set_kernels(vec![vec![0f64; 4usize], vec![1f64; 4usize]]).unwrap();
+        kernelcanvas
     }
 
-    fn clean_canvas(&mut self) {
-        for active in &mut self.active_kernels {
-            *active = false;
-        }
+    /// Frames lying on the kernels of the given indices
+    fn frames_on(kernel_indices: &[usize]) -> Vec<Vec<f64>> {
+        kernel_indices.iter().map(|index| vec![*index as f64; 4usize]).collect()
+    }
+
+    #[test]
+    fn retina_has_a_region_per_time_segment_in_every_block() {
+        let mut kernelcanvas = KernelCanvas::new(8usize, 2usize, 3usize, 4usize);
+        assert_eq!(kernelcanvas.retina_size(), 8usize * 4usize * 3usize);
+
+        let frames = (0..6).map(|index| vec![index as f64, (index * index) as f64]).collect();
+        kernelcanvas.process(frames);
+        assert_eq!(kernelcanvas.get_painted_canvas().len(), kernelcanvas.retina_size());
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn frames_land_in_their_time_segment() {
+        let mut kernelcanvas = two_kernel_canvas(1usize, 2usize);
+
+        // Frame i of 5 goes to segment i * 2 / 5, so frames 0 to 2 to the first one
+        kernelcanvas.processed_frames = frames_on(&[0usize, 0usize, 1usize, 1usize, 1usize]);
+        kernelcanvas.paint_canvas();
+        assert_eq!(kernelcanvas.kernel_hits, vec![2u64, 1u64, 0u64, 2u64]);
+    }
+
+    #[test]
+    fn only_segmented_canvases_tell_reversed_frames_apart() {
+        let mut segmented = two_kernel_canvas(2usize, 2usize);
+        segmented.processed_frames = frames_on(&[0usize, 0usize, 1usize, 1usize]);
+        let forward = segmented.get_painted_canvas();
+        segmented.processed_frames = frames_on(&[1usize, 1usize, 0usize, 0usize]);
+        let reversed = segmented.get_painted_canvas();
+
+        assert_eq!(forward, vec![true, false, false, true, true, false, false, true]);
+        assert_ne!(forward, reversed);
+
+        let mut single = two_kernel_canvas(2usize, 1usize);
+        single.processed_frames = frames_on(&[0usize, 0usize, 1usize, 1usize]);
+        let forward = single.get_painted_canvas();
+        single.processed_frames = frames_on(&[1usize, 1usize, 0usize, 0usize]);
+        assert_eq!(forward, single.get_painted_canvas());
+    }
+
+    #[test]
+    fn replacing_kernels_drops_distance_thresholds() {
+        let mut kernelcanvas = two_kernel_canvas(1usize, 1usize);
+        kernelcanvas.fit_distance_thresholds(&[vec![1f64, 2f64]]);
+        assert!(kernelcanvas.distance_thresholds.is_some());
+
+        kernelcanvas.learn_kernels(&[], KernelLearning::Random);
+        assert!(kernelcanvas.distance_thresholds.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Seeding would replace kernels that were learned or imported")]
+    fn seeding_keeps_set_kernels() {
+        two_kernel_canvas(1usize, 1usize).set_seed(1usize);
+    }
+
+    #[test]
+    #[should_panic(expected = "A canvas needs at least one time segment")]
+    fn canvas_without_time_segments_is_rejected() {
+        KernelCanvas::new(2usize, 1usize, 1usize, 0usize);
+    }
+}
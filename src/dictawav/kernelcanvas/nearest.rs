@@ -0,0 +1,401 @@
+use std::f64;
+use super::kernel::Kernel;
+use super::metric::BaseDistance;
+
+/// Kernels stored in a leaf of the KD-tree
+const KD_TREE_LEAF_SIZE: usize = 8;
+
+/// How the nearest kernel of a frame is searched
+///
+/// Every search is exact: it returns the same kernel as the brute force scan, including the lowest
+/// index when several kernels are at the same distance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NearestKernelSearch {
+    /// Scans every kernel in turn
+    BruteForce,
+    /// Scans every kernel with the coordinates stored dimension by dimension, so the distances to
+    /// consecutive kernels are computed in lockstep and can use SIMD
+    FlatScan,
+    /// Prunes kernels with a KD-tree
+    KdTree,
+}
+
+/// Search structure built over the kernels of a canvas
+pub enum KernelIndex {
+    BruteForce(BaseDistance),
+    FlatScan(FlatKernels),
+    KdTree(KdTree),
+}
+
+impl Default for KernelIndex {
+    fn default() -> KernelIndex {
+        KernelIndex::BruteForce(BaseDistance::SquaredEuclidean)
+    }
+}
+
+impl KernelIndex {
+    pub fn new(search: NearestKernelSearch, kernels: &[Kernel], base: BaseDistance) -> KernelIndex {
+        match search {
+            NearestKernelSearch::BruteForce => KernelIndex::BruteForce(base),
+            NearestKernelSearch::FlatScan => KernelIndex::FlatScan(FlatKernels::new(kernels, base)),
+            NearestKernelSearch::KdTree => KernelIndex::KdTree(KdTree::new(kernels, base)),
+        }
+    }
+
+    pub fn nearest(&self, kernels: &[Kernel], frame: &[f64]) -> usize {
+        match *self {
+            KernelIndex::BruteForce(base) => brute_force_nearest(kernels, frame, base),
+            KernelIndex::FlatScan(ref flat_kernels) => flat_kernels.nearest(frame),
+            KernelIndex::KdTree(ref kd_tree) => kd_tree.nearest(kernels, frame),
+        }
+    }
+
+    /// Base distances to every kernel
+    pub fn distances(&self, kernels: &[Kernel], frame: &[f64]) -> Vec<f64> {
+        match *self {
+            KernelIndex::BruteForce(base) => brute_force_distances(kernels, frame, base),
+            KernelIndex::FlatScan(ref flat_kernels) => flat_kernels.distances(frame),
+            KernelIndex::KdTree(ref kd_tree) => brute_force_distances(kernels, frame, kd_tree.base),
+        }
+    }
+
+    /// The `k` nearest kernels, from the nearest to the farthest
+    pub fn k_nearest(&self, kernels: &[Kernel], frame: &[f64], k: usize) -> Vec<usize> {
+        match *self {
+            KernelIndex::KdTree(ref kd_tree) => kd_tree.k_nearest(kernels, frame, k),
+            _ => k_smallest(&self.distances(kernels, frame), k),
+        }
+    }
+
+    /// Every kernel whose base distance is at most `bound`, in increasing index order
+    pub fn within_radius(&self, kernels: &[Kernel], frame: &[f64], bound: f64) -> Vec<usize> {
+        match *self {
+            KernelIndex::KdTree(ref kd_tree) => kd_tree.within_radius(kernels, frame, bound),
+            _ => not_above(&self.distances(kernels, frame), bound),
+        }
+    }
+}
+
+fn brute_force_distances(kernels: &[Kernel], frame: &[f64], base: BaseDistance) -> Vec<f64> {
+    kernels.iter().map(|kernel| kernel.distance_to(frame, base)).collect()
+}
+
+/// Indices of the `k` smallest distances, ties broken by the lowest index
+///
+/// Only the `k` selected ones are sorted, NaN distances coming after every other one.
+fn k_smallest(distances: &[f64], k: usize) -> Vec<usize> {
+    if k == 0usize {
+        return Vec::new();
+    }
+    let order = |a: &usize, b: &usize| distances[*a].total_cmp(&distances[*b]).then(a.cmp(b));
+
+    let mut indices: Vec<usize> = (0..distances.len()).collect();
+    if k < indices.len() {
+        indices.select_nth_unstable_by(k - 1usize, order);
+        indices.truncate(k);
+    }
+    indices.sort_unstable_by(order);
+    indices
+}
+
+fn not_above(distances: &[f64], bound: f64) -> Vec<usize> {
+    distances.iter()
+             .enumerate()
+             .filter(|&(_, distance)| *distance <= bound)
+             .map(|(index, _)| index)
+             .collect()
+}
+
+pub fn brute_force_nearest(kernels: &[Kernel], frame: &[f64], base: BaseDistance) -> usize {
+    let mut nearest_kernel_index = 0usize;
+    let mut nearest_kernel_distance = f64::MAX;
+
+    for (index, kernel) in kernels.iter().enumerate() {
+        let distance = kernel.distance_to(frame, base);
+        if distance < nearest_kernel_distance {
+            nearest_kernel_distance = distance;
+            nearest_kernel_index = index;
+        }
+    }
+
+    nearest_kernel_index
+}
+
+/// Kernel coordinates in a single dimension-major buffer
+pub struct FlatKernels {
+    kernel_count: usize,
+    coordinates: Vec<f64>,
+    base: BaseDistance,
+}
+
+impl FlatKernels {
+    pub fn new(kernels: &[Kernel], base: BaseDistance) -> FlatKernels {
+        let kernel_count = kernels.len();
+        let dimension = kernels.first().map_or(0usize, |kernel| kernel.coordinates().len());
+
+        let mut coordinates = vec![0f64; kernel_count * dimension];
+        for (kernel_index, kernel) in kernels.iter().enumerate() {
+            for (dimension_index, coordinate) in kernel.coordinates().iter().enumerate() {
+                coordinates[dimension_index * kernel_count + kernel_index] = *coordinate;
+            }
+        }
+
+        FlatKernels {
+            kernel_count,
+            coordinates,
+            base,
+        }
+    }
+
+    /// Base distances to every kernel
+    pub fn distances(&self, frame: &[f64]) -> Vec<f64> {
+        // Each distance is accumulated dimension by dimension, as the brute force scan does
+        let mut distances = vec![0f64; self.kernel_count];
+        for (value, row) in frame.iter().zip(self.coordinates.chunks(self.kernel_count)) {
+            for (distance, coordinate) in distances.iter_mut().zip(row.iter()) {
+                *distance = self.base.accumulate(*distance, *coordinate - *value);
+            }
+        }
+
+        distances
+    }
+
+    pub fn nearest(&self, frame: &[f64]) -> usize {
+        let mut nearest_kernel_index = 0usize;
+        let mut nearest_kernel_distance = f64::MAX;
+        for (index, distance) in self.distances(frame).into_iter().enumerate() {
+            if distance < nearest_kernel_distance {
+                nearest_kernel_distance = distance;
+                nearest_kernel_index = index;
+            }
+        }
+
+        nearest_kernel_index
+    }
+}
+
+enum KdNode {
+    Leaf(Vec<usize>),
+    Split {
+        dimension: usize,
+        value: f64,
+        lower: Box<KdNode>,
+        upper: Box<KdNode>,
+    },
+}
+
+pub struct KdTree {
+    root: KdNode,
+    base: BaseDistance,
+}
+
+impl KdTree {
+    pub fn new(kernels: &[Kernel], base: BaseDistance) -> KdTree {
+        let indices = (0..kernels.len()).collect();
+
+        KdTree {
+            root: KdTree::build(kernels, indices),
+            base,
+        }
+    }
+
+    /// Splits on the dimension with the largest spread, at its median
+    fn build(kernels: &[Kernel], mut indices: Vec<usize>) -> KdNode {
+        if indices.len() <= KD_TREE_LEAF_SIZE {
+            return KdNode::Leaf(indices);
+        }
+
+        let dimension_count = kernels[indices[0]].coordinates().len();
+        let mut dimension = 0usize;
+        let mut largest_spread = -1f64;
+        for current_dimension in 0..dimension_count {
+            let mut minimum = f64::MAX;
+            let mut maximum = f64::MIN;
+            for index in &indices {
+                let coordinate = kernels[*index].coordinates()[current_dimension];
+                minimum = minimum.min(coordinate);
+                maximum = maximum.max(coordinate);
+            }
+            if maximum - minimum > largest_spread {
+                largest_spread = maximum - minimum;
+                dimension = current_dimension;
+            }
+        }
+
+        if largest_spread <= 0f64 {
+            // Every remaining kernel is at the same place
+            return KdNode::Leaf(indices);
+        }
+
+        indices.sort_by(
+            |a, b|
+                kernels[*a].coordinates()[dimension].total_cmp(&kernels[*b].coordinates()[dimension])
+        );
+        let upper_indices = indices.split_off(indices.len() / 2usize);
+        let value = kernels[upper_indices[0]].coordinates()[dimension];
+
+        KdNode::Split {
+            dimension,
+            value,
+            lower: Box::new(KdTree::build(kernels, indices)),
+            upper: Box::new(KdTree::build(kernels, upper_indices)),
+        }
+    }
+
+    pub fn nearest(&self, kernels: &[Kernel], frame: &[f64]) -> usize {
+        let mut best = (f64::MAX, usize::MAX);
+        self.search(&self.root, kernels, frame, &mut best);
+        if best.1 == usize::MAX {
+            // No distance compares with a NaN frame, the brute force scan keeps the first kernel
+            return brute_force_nearest(kernels, frame, self.base);
+        }
+        best.1
+    }
+
+    fn search(&self, node: &KdNode, kernels: &[Kernel], frame: &[f64], best: &mut (f64, usize)) {
+        match *node {
+            KdNode::Leaf(ref indices) => {
+                for index in indices {
+                    let distance = kernels[*index].distance_to(frame, self.base);
+                    if distance < best.0 || (distance == best.0 && *index < best.1) {
+                        *best = (distance, *index);
+                    }
+                }
+            }
+            KdNode::Split { dimension, value, ref lower, ref upper } => {
+                let difference = frame[dimension] - value;
+                let (near, far) = if difference < 0f64 { (lower, upper) } else { (upper, lower) };
+
+                self.search(near, kernels, frame, best);
+                // Equal bounds are still visited, a kernel with a lower index may tie
+                if self.base.axis_bound(difference) <= best.0 {
+                    self.search(far, kernels, frame, best);
+                }
+            }
+        }
+    }
+
+    pub fn k_nearest(&self, kernels: &[Kernel], frame: &[f64], k: usize) -> Vec<usize> {
+        if frame.iter().any(|value| value.is_nan()) {
+            // Pruning can't order NaN distances as the scans do
+            return k_smallest(&brute_force_distances(kernels, frame, self.base), k);
+        }
+        let mut best = Vec::with_capacity(k + 1usize);
+        if k > 0usize {
+            self.search_k(&self.root, kernels, frame, k, &mut best);
+        }
+        best.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Keeps `best` sorted by distance then index, holding at most `k` kernels
+    fn search_k(&self, node: &KdNode, kernels: &[Kernel], frame: &[f64], k: usize, best: &mut Vec<(f64, usize)>) {
+        match *node {
+            KdNode::Leaf(ref indices) => {
+                for index in indices {
+                    let candidate = (kernels[*index].distance_to(frame, self.base), *index);
+                    let position = best.iter()
+                                       .position(|&(distance, kernel)| candidate.0 < distance || (candidate.0 == distance && candidate.1 < kernel))
+                                       .unwrap_or(best.len());
+                    if position < k {
+                        best.insert(position, candidate);
+                        best.truncate(k);
+                    }
+                }
+            }
+            KdNode::Split { dimension, value, ref lower, ref upper } => {
+                let difference = frame[dimension] - value;
+                let (near, far) = if difference < 0f64 { (lower, upper) } else { (upper, lower) };
+
+                self.search_k(near, kernels, frame, k, best);
+                if best.len() < k || self.base.axis_bound(difference) <= best[best.len() - 1usize].0 {
+                    self.search_k(far, kernels, frame, k, best);
+                }
+            }
+        }
+    }
+
+    pub fn within_radius(&self, kernels: &[Kernel], frame: &[f64], bound: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.search_radius(&self.root, kernels, frame, bound, &mut found);
+        found.sort();
+        found
+    }
+
+    fn search_radius(&self, node: &KdNode, kernels: &[Kernel], frame: &[f64], bound: f64, found: &mut Vec<usize>) {
+        match *node {
+            KdNode::Leaf(ref indices) => {
+                found.extend(indices.iter().filter(|index| kernels[**index].distance_to(frame, self.base) <= bound));
+            }
+            KdNode::Split { dimension, value, ref lower, ref upper } => {
+                let difference = frame[dimension] - value;
+                let (near, far) = if difference < 0f64 { (lower, upper) } else { (upper, lower) };
+
+                self.search_radius(near, kernels, frame, bound, found);
+                if self.base.axis_bound(difference) <= bound {
+                    self.search_radius(far, kernels, frame, bound, found);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::{Rng, SeedableRng, StdRng};
+    use super::*;
+    use super::super::kernel::KernelBuilder;
+
+    #[test]
+    fn searches_match_brute_force() {
+        let dimension = 6usize;
+        let builder = KernelBuilder::new(dimension);
+        let mut random_generator: StdRng = SeedableRng::from_seed(&[3usize][..]);
+
+        let mut coordinates: Vec<Vec<f64>> = (0..300).map(
+            |_| (0..dimension).map(|_| (random_generator.gen_range(-4i32, 5i32) as f64) / 4f64).collect()
+        ).collect();
+        // Duplicated kernels force ties
+        let duplicate = coordinates[10].clone();
+        coordinates.push(duplicate);
+        let kernels: Vec<Kernel> = builder.build_from_coordinates(coordinates).unwrap();
+
+        for base in &[BaseDistance::SquaredEuclidean, BaseDistance::Manhattan, BaseDistance::Chebyshev] {
+            let indices: Vec<KernelIndex> = [NearestKernelSearch::FlatScan, NearestKernelSearch::KdTree]
+                .iter()
+                .map(|search| KernelIndex::new(*search, &kernels, *base))
+                .collect();
+
+            for _ in 0..500 {
+                let frame: Vec<f64> = (0..dimension).map(|_| (random_generator.gen_range(-5i32, 6i32) as f64) / 4f64).collect();
+                let expected = brute_force_nearest(&kernels, &frame, *base);
+
+                let expected_k_nearest = k_smallest(&brute_force_distances(&kernels, &frame, *base), 5usize);
+                let expected_within_radius = not_above(&brute_force_distances(&kernels, &frame, *base), 0.75f64);
+
+                for index in &indices {
+                    assert_eq!(index.nearest(&kernels, &frame), expected);
+                    assert_eq!(index.k_nearest(&kernels, &frame, 5usize), expected_k_nearest);
+                    assert_eq!(index.within_radius(&kernels, &frame, 0.75f64), expected_within_radius);
+                }
+                assert_eq!(expected_k_nearest[0], expected);
+            }
+
+            let nan_frame = vec![f64::NAN; dimension];
+            for index in &indices {
+                assert_eq!(index.nearest(&kernels, &nan_frame), brute_force_nearest(&kernels, &nan_frame, *base));
+                assert_eq!(index.k_nearest(&kernels, &nan_frame, 5usize), k_smallest(&brute_force_distances(&kernels, &nan_frame, *base), 5usize));
+            }
+        }
+    }
+
+    #[test]
+    fn k_smallest_selects_by_distance_then_index() {
+        let distances = [3f64, 1f64, f64::NAN, 1f64, 0.5f64];
+
+        assert_eq!(k_smallest(&distances, 0usize), Vec::<usize>::new());
+        assert_eq!(k_smallest(&distances, 3usize), vec![4usize, 1usize, 3usize]);
+        assert_eq!(k_smallest(&distances, 10usize), vec![4usize, 1usize, 3usize, 0usize, 2usize]);
+    }
+}
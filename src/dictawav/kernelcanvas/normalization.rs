@@ -0,0 +1,211 @@
+/// Where the cepstral mean and variance statistics come from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Normalization {
+    /// Each utterance is normalised with its own mean and standard deviation
+    PerUtterance,
+    /// Statistics learned over the whole training set
+    Global,
+    /// Statistics learned over the utterances of the current speaker
+    PerSpeaker,
+}
+
+/// Nonlinearity applied to each feature after normalisation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Nonlinearity {
+    /// Keeps the normalised values as they are
+    Identity,
+    /// Squashes the normalised values into (-1, 1)
+    Tanh,
+}
+
+impl Nonlinearity {
+    #[inline]
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            Nonlinearity::Identity => value,
+            Nonlinearity::Tanh => value.tanh(),
+        }
+    }
+}
+
+/// Mean and standard deviation of each feature dimension
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CmvnStatistics {
+    means: Vec<f64>,
+    std_deviations: Vec<f64>,
+}
+
+impl CmvnStatistics {
+    /// Computes the statistics of a single utterance
+    pub fn from_frames(frames: &[Vec<f64>]) -> CmvnStatistics {
+        let mut accumulator = CmvnAccumulator::new();
+        accumulator.add_frames(frames);
+        accumulator.finish()
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.means.len()
+    }
+
+    /// Normalises a frame, dimensions without variance are only centered
+    pub fn apply(&self, frame: &[f64], nonlinearity: Nonlinearity) -> Vec<f64> {
+        frame.iter()
+             .zip(self.means.iter().zip(self.std_deviations.iter()))
+             .map(
+                 |(value, (mean, std_deviation))|
+                     if *std_deviation > 0f64 {
+                         nonlinearity.apply((*value - *mean) / *std_deviation)
+                     } else {
+                         nonlinearity.apply(*value - *mean)
+                     }
+             ).collect()
+    }
+
+    pub fn normalize_frames(&self, frames: &[Vec<f64>], nonlinearity: Nonlinearity) -> Vec<Vec<f64>> {
+        frames.iter().map(|frame| self.apply(frame, nonlinearity)).collect()
+    }
+}
+
+/// Accumulates running means and squared deviations of frames to compute statistics over many
+/// utterances
+///
+/// Welford's update keeps large values with a small variance from cancelling out, as sums of
+/// squares would.
+#[derive(Clone, Debug, Default)]
+pub struct CmvnAccumulator {
+    frames_count: usize,
+    means: Vec<f64>,
+    squared_deviations: Vec<f64>,
+}
+
+impl CmvnAccumulator {
+    pub fn new() -> CmvnAccumulator {
+        CmvnAccumulator::default()
+    }
+
+    pub fn add_frames(&mut self, frames: &[Vec<f64>]) {
+        for frame in frames {
+            self.check_dimension(frame.len());
+
+            self.frames_count += 1usize;
+            let count = self.frames_count as f64;
+            for (index, value) in frame.iter().enumerate() {
+                let deviation = *value - self.means[index];
+                self.means[index] += deviation / count;
+                self.squared_deviations[index] += deviation * (*value - self.means[index]);
+            }
+        }
+    }
+
+    /// Adds the frames another accumulator saw, as Chan et al. combine partial variances
+    pub fn merge(&mut self, other: &CmvnAccumulator) {
+        if other.frames_count == 0usize {
+            return;
+        }
+        self.check_dimension(other.means.len());
+
+        let count = self.frames_count as f64;
+        let other_count = other.frames_count as f64;
+        let total_count = count + other_count;
+        for index in 0..self.means.len() {
+            let deviation = other.means[index] - self.means[index];
+            self.means[index] += deviation * other_count / total_count;
+            self.squared_deviations[index] += other.squared_deviations[index] + deviation * deviation * count * other_count / total_count;
+        }
+        self.frames_count += other.frames_count;
+    }
+
+    fn check_dimension(&mut self, dimension: usize) {
+        if self.frames_count == 0usize {
+            self.means = vec![0f64; dimension];
+            self.squared_deviations = vec![0f64; dimension];
+        }
+        if dimension != self.means.len() {
+            panic!("KernelCanvas Error: Trying to accumulate statistics of frames with different dimension!");
+        }
+    }
+
+    pub fn frames_count(&self) -> usize {
+        self.frames_count
+    }
+
+    /// Computes the statistics using the unbiased sample variance
+    pub fn finish(&self) -> CmvnStatistics {
+        let degrees_of_freedom = if self.frames_count > 1usize { self.frames_count as f64 - 1f64 } else { 1f64 };
+
+        let std_deviations = self.squared_deviations
+                                 .iter()
+                                 .map(|squared_deviation| (*squared_deviation / degrees_of_freedom).sqrt())
+                                 .collect();
+
+        CmvnStatistics {
+            means: self.means.clone(),
+            std_deviations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_use_standard_deviation() {
+        let frames = vec![vec![1f64, 10f64], vec![3f64, 10f64], vec![5f64, 10f64]];
+        let statistics = CmvnStatistics::from_frames(&frames);
+
+        assert_eq!(statistics.means, vec![3f64, 10f64]);
+        assert_eq!(statistics.std_deviations, vec![2f64, 0f64]);
+        assert_eq!(statistics.apply(&frames[2], Nonlinearity::Identity), vec![1f64, 0f64]);
+    }
+
+    #[test]
+    fn accumulator_matches_single_pass() {
+        let first = vec![vec![1f64], vec![2f64]];
+        let second = vec![vec![4f64], vec![9f64]];
+
+        let mut accumulator = CmvnAccumulator::new();
+        accumulator.add_frames(&first);
+        accumulator.add_frames(&second);
+
+        let all_frames: Vec<Vec<f64>> = first.into_iter().chain(second).collect();
+        let expected = CmvnStatistics::from_frames(&all_frames);
+        let statistics = accumulator.finish();
+
+        assert_eq!(accumulator.frames_count(), 4usize);
+        assert!((statistics.means[0] - expected.means[0]).abs() < 1e-12);
+        assert!((statistics.std_deviations[0] - expected.std_deviations[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn large_values_keep_their_small_variance() {
+        let frames: Vec<Vec<f64>> = (0..1000).map(|index| vec![1e9f64 + f64::from(index % 3)]).collect();
+        let statistics = CmvnStatistics::from_frames(&frames);
+
+        assert!((statistics.means[0] - (1e9f64 + 0.999f64)).abs() < 1e-6);
+        assert!((statistics.std_deviations[0] - (666.999f64 / 999f64).sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn merged_accumulators_match_a_single_one() {
+        let first = vec![vec![1f64, -2f64], vec![2f64, 0f64], vec![6f64, 5f64]];
+        let second = vec![vec![4f64, 1f64], vec![9f64, 3f64]];
+
+        let mut merged = CmvnAccumulator::new();
+        merged.add_frames(&first);
+        let mut other = CmvnAccumulator::new();
+        other.add_frames(&second);
+        merged.merge(&other);
+        merged.merge(&CmvnAccumulator::new());
+
+        let all_frames: Vec<Vec<f64>> = first.into_iter().chain(second).collect();
+        let expected = CmvnStatistics::from_frames(&all_frames);
+        let statistics = merged.finish();
+
+        assert_eq!(merged.frames_count(), 5usize);
+        for dimension in 0..2usize {
+            assert!((statistics.means[dimension] - expected.means[dimension]).abs() < 1e-12);
+            assert!((statistics.std_deviations[dimension] - expected.std_deviations[dimension]).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+/// Which kernels a frame activates on the canvas
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PaintingMode {
+    /// Only the nearest kernel
+    Nearest,
+    /// The `k` nearest kernels
+    KNearest { k: usize },
+    /// Every kernel within `radius` of the frame, and always the nearest one
+    Radius { radius: f64 },
+}
+
+/// How the marks left by an utterance on each kernel become the `output_factor` blocks of the retina
+///
+/// With a segmented canvas each time segment counts as its own set of kernels inside a block.
+/// Distance based encodings use, for each kernel, the distance to the nearest frame of the utterance.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CanvasEncoding {
+    /// Every block holds the same active kernels
+    Repetition,
+    /// Block `j` holds the kernels hit more than `j * step` times
+    HitThermometer { step: u64 },
+    /// Block `j` holds the kernels nearer than `max_distance * (output_factor - j) / output_factor`
+    DistanceThermometer { max_distance: f64 },
+    /// The nearest distance is quantised in `2^output_factor` levels up to `max_distance`, and bit `j`
+    /// of the Gray code of its level goes to block `j`
+    ///
+    /// Kernels no frame came near, in a time segment without frames, have no bit set.
+    GrayDistanceLevel { max_distance: f64 },
+    /// Block `j` holds the kernels nearer than their own `j`-th threshold, learned over training data
+    DistanceThresholds,
+}
+
+impl CanvasEncoding {
+    pub fn needs_distances(self) -> bool {
+        !matches!(self, CanvasEncoding::Repetition | CanvasEncoding::HitThermometer { .. })
+    }
+
+    /// Builds the retina, block by block, each block with one bit per kernel of every segment
+    ///
+    /// `nearest_distances` is only read by distance based encodings, and `distance_thresholds`, one
+    /// list of `output_factor` thresholds per kernel, only by `DistanceThresholds`.
+    pub fn encode(
+        self,
+        kernel_hits: &[u64],
+        nearest_distances: &[f64],
+        distance_thresholds: Option<&[Vec<f64>]>,
+        output_factor: usize,
+    ) -> Vec<bool> {
+        let mut painted_canvas = Vec::with_capacity(kernel_hits.len() * output_factor);
+
+        for level in 0..output_factor {
+            match self {
+                CanvasEncoding::Repetition => {
+                    painted_canvas.extend(kernel_hits.iter().map(|hits| *hits > 0u64));
+                }
+                CanvasEncoding::HitThermometer { step } => {
+                    let threshold = level as u64 * step;
+                    painted_canvas.extend(kernel_hits.iter().map(|hits| *hits > threshold));
+                }
+                CanvasEncoding::DistanceThermometer { max_distance } => {
+                    let threshold = max_distance * (output_factor - level) as f64 / output_factor as f64;
+                    painted_canvas.extend(nearest_distances.iter().map(|distance| *distance < threshold));
+                }
+                CanvasEncoding::GrayDistanceLevel { max_distance } => {
+                    let levels_count = 1u64 << output_factor.min(63usize);
+                    painted_canvas.extend(nearest_distances.iter().map(
+                        |distance| {
+                            if distance.is_infinite() {
+                                return false;
+                            }
+                            let quantised = (distance / max_distance * levels_count as f64).max(0f64);
+                            let level_index = if quantised >= levels_count as f64 { levels_count - 1u64 } else { quantised as u64 };
+                            let gray_code = level_index ^ (level_index >> 1);
+                            level < 64usize && (gray_code >> level) & 1u64 == 1u64
+                        }
+                    ));
+                }
+                CanvasEncoding::DistanceThresholds => {
+                    let thresholds = match distance_thresholds {
+                        Some(thresholds) => thresholds,
+                        None => panic!("KernelCanvas Error: Distance thresholds encoding requires thresholds learned over training data!"),
+                    };
+                    let kernel_count = thresholds.len();
+                    painted_canvas.extend(nearest_distances.iter().enumerate().map(
+                        |(position, distance)|
+                            *distance <= thresholds[position % kernel_count][level]
+                    ));
+                }
+            }
+        }
+
+        painted_canvas
+    }
+}
+
+/// For each kernel, `output_factor` quantiles of its nearest distances over many utterances
+pub fn distance_quantiles(nearest_distances: &[Vec<f64>], kernel_count: usize, output_factor: usize) -> Vec<Vec<f64>> {
+    if nearest_distances.is_empty() {
+        panic!("KernelCanvas Error: Trying to learn distance thresholds without any utterance!");
+    }
+
+    (0..kernel_count).map(
+        |kernel| {
+            let mut distances: Vec<f64> = nearest_distances.iter().map(|utterance| utterance[kernel]).collect();
+            distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            (0..output_factor).map(
+                |level| {
+                    let position = (level + 1usize) * distances.len() / (output_factor + 1usize);
+                    distances[position.min(distances.len() - 1usize)]
+                }
+            ).collect()
+        }
+    ).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thermometer_keeps_repetition_layout() {
+        let kernel_hits = [0u64, 1u64, 3u64];
+
+        assert_eq!(
+            CanvasEncoding::Repetition.encode(&kernel_hits, &[], None, 2usize),
+            vec![false, true, true, false, true, true]
+        );
+        assert_eq!(
+            CanvasEncoding::HitThermometer { step: 2u64 }.encode(&kernel_hits, &[], None, 3usize),
+            vec![false, true, true, false, false, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn distance_encodings() {
+        let kernel_hits = [1u64, 0u64, 0u64];
+        let nearest_distances = [0.1f64, 0.6f64, 2f64];
+
+        assert_eq!(
+            CanvasEncoding::DistanceThermometer { max_distance: 1f64 }.encode(&kernel_hits, &nearest_distances, None, 2usize),
+            vec![true, true, false, true, false, false]
+        );
+        // Levels 0, 2 and 3 have Gray codes 00, 11 and 10
+        assert_eq!(
+            CanvasEncoding::GrayDistanceLevel { max_distance: 1f64 }.encode(&kernel_hits, &nearest_distances, None, 2usize),
+            vec![false, true, false, false, true, true]
+        );
+        // The farthest level sets the top bit, a kernel no frame came near sets none
+        assert_eq!(
+            CanvasEncoding::GrayDistanceLevel { max_distance: 1f64 }.encode(&[0u64, 0u64], &[2f64, f64::INFINITY], None, 2usize),
+            vec![false, false, true, false]
+        );
+
+        let thresholds = distance_quantiles(&[vec![1f64, 1f64, 1f64], vec![3f64, 0.5f64, 1f64]], 3usize, 1usize);
+        assert_eq!(thresholds, vec![vec![3f64], vec![1f64], vec![1f64]]);
+        assert_eq!(
+            CanvasEncoding::DistanceThresholds.encode(&kernel_hits, &nearest_distances, Some(&thresholds), 1usize),
+            vec![true, true, false]
+        );
+    }
+}
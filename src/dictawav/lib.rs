@@ -1,23 +1,77 @@
-use self::kernelcanvas::KernelCanvas;
+extern crate bincode;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+use self::feedback::CorrectionLog;
+use self::heads::Heads;
+use self::kernelcanvas::CmvnAccumulator;
 use self::preprocessor::PreProcessor;
 use self::wav_handler::WavHandler;
-use self::wisard::Wisard;
+use self::wisard::{RegressionWisard, Wisard};
+pub use self::statistics::ModelStatistics;
+pub use self::wisard::{Aggregation, BleachingPolicy, ClassStatistics, Clustering, CounterWidth, ForgetError, Prediction, RamMerge, VocabularyError, WisardStatistics};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
 use std::path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+mod augmentation;
+mod diagnostics;
+mod ensemble;
+mod explanation;
+mod feedback;
+mod heads;
+mod noise_benchmark;
+mod statistics;
 mod wav_handler;
 mod preprocessor;
 mod kernelcanvas;
 mod wisard;
 
+pub use self::augmentation::{Augmentation, Augmenter, NoiseColor};
+pub use self::diagnostics::GrayImage;
+pub use self::ensemble::{Ensemble, EnsemblePrediction, EnsembleVoting};
+pub use self::explanation::{CanvasBit, ClassExplanation, Explanation, RamVote};
+pub use self::feedback::{Correction, FeedbackError, FeedbackOptions};
+pub use self::heads::HierarchicalPrediction;
+pub use self::noise_benchmark::{BenchmarkNoise, NoiseBenchmark};
+pub use self::kernelcanvas::{CanvasEncoding, CmvnStatistics, DistanceMetric, KernelCanvas, KernelFormat, KernelLearning, KernelSetError, NearestKernelSearch, Nonlinearity, Normalization, PaintingMode};
+pub use self::preprocessor::{CepstralConfig, FilterbankConfig, FrontEnd, MelScale};
+
+const MODEL_MAGIC: &[u8; 8] = b"DICTAWAV";
+const MODEL_VERSION: u32 = 10;
+
+// Tells apart the temporary files of concurrent saves within a process
+static TEMPORARY_SAVES: AtomicUsize = AtomicUsize::new(0usize);
+
+#[derive(Serialize, Deserialize)]
 pub struct DictaWav {
+    front_end: FrontEnd,
     kernelcanvas: KernelCanvas,
     wisard: Wisard,
+    // Only created by the first regression target trained
+    regression_wisard: Option<RegressionWisard>,
+    regression_aggregation: Aggregation,
+    heads: Heads,
+    #[serde(skip)]
+    augmenter: Option<Augmenter>,
+    #[serde(skip)]
+    feedback_options: FeedbackOptions,
+    #[serde(skip)]
+    correction_log: CorrectionLog,
+    // Metric the canvas used when the WiSARD was first trained
+    trained_distance_metric: Option<DistanceMetric>,
 }
 
 impl DictaWav {
-    pub fn new(kernelcanvas_kernels_count: usize,
-               kernelcanvas_kernels_dimension: usize,
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(front_end: FrontEnd,
+               kernelcanvas_kernels_count: usize,
                kernelcanvas_output_factor: usize,
+               kernelcanvas_time_segments: usize,
                wisard_retina_size: usize,
                wisard_ram_num_bits: usize,
                wisard_use_bleaching: bool,
@@ -28,9 +82,14 @@ impl DictaWav {
     ) -> DictaWav {
         let kernelcanvas = KernelCanvas::new(
             kernelcanvas_kernels_count,
-            kernelcanvas_kernels_dimension,
+            front_end.output_dimension(),
             kernelcanvas_output_factor,
+            kernelcanvas_time_segments,
         );
+        if kernelcanvas.retina_size() != wisard_retina_size {
+            panic!("DictaWav Error: WiSARD retina size must be kernels count * time segments * output factor!");
+        }
+
         let wisard = Wisard::new(
             wisard_retina_size,
             wisard_ram_num_bits,
@@ -42,19 +101,392 @@ impl DictaWav {
         );
 
         DictaWav {
+            front_end,
             kernelcanvas,
             wisard,
+            regression_wisard: None,
+            regression_aggregation: Aggregation::Mean,
+            heads: Heads::new(),
+            augmenter: None,
+            feedback_options: FeedbackOptions::default(),
+            correction_log: CorrectionLog::default(),
+            trained_distance_metric: None,
+        }
+    }
+
+    /// Selects how bleaching picks the threshold ram values must exceed to vote, the default is
+    /// `BleachingPolicy::MinimumConfidence`
+    pub fn set_bleaching_policy(&mut self, bleaching_policy: BleachingPolicy) {
+        self.wisard.set_bleaching_policy(bleaching_policy);
+    }
+
+    /// Draws the random kernels and shuffles the ram address mapping, which the regression WiSARD
+    /// shares, from a seed, so models built the same way with the same seed are the same
+    ///
+    /// It must be called before learning kernels and training.
+    pub fn set_seed(&mut self, seed: usize) {
+        self.check_untrained("Seeding the model");
+        self.kernelcanvas.set_seed(seed);
+        self.wisard.set_seed(seed);
+    }
+
+    /// Selects the width of the saturating ram counters, the default is `CounterWidth::U32`
+    ///
+    /// Rams of up to 10 bits keep a counter for every address, wider ones only for the addresses
+    /// they stored. It must be set before training.
+    pub fn set_ram_counter_width(&mut self, counter_width: CounterWidth) {
+        self.wisard.set_counter_width(counter_width);
+    }
+
+    /// Lets every class learn with up to `max_discriminators` discriminators (ClusWiSARD)
+    ///
+    /// A sample scoring below `score_threshold` on every discriminator of its class starts a new
+    /// one, and classification uses the discriminator of each class recognising the retina best.
+    /// It must be set before training.
+    pub fn set_clustering(&mut self, clustering: Option<Clustering>) {
+        self.wisard.set_clustering(clustering);
+    }
+
+    /// Training examples, ram occupancy, counter values and memory of every class, and how much of
+    /// the canvas is used
+    pub fn statistics(&self) -> ModelStatistics {
+        ModelStatistics {
+            wisard: self.wisard.statistics(),
+            kernels_count: self.kernelcanvas.get_kernel_count(),
+            activated_kernels_count: self.kernelcanvas.activated_kernels_count(),
         }
     }
 
+    /// Heap memory taken by the rams of every class, sorted by class name
+    pub fn class_memory_bytes(&self) -> Vec<(String, usize)> {
+        self.wisard.class_memory_bytes()
+    }
+
+    /// Heap memory taken by the WiSARD, the regression WiSARD and the heads, the canvas kernels not
+    /// included
+    pub fn memory_bytes(&self) -> usize {
+        self.wisard.memory_bytes()
+            + self.regression_wisard.as_ref().map_or(0usize, |regression_wisard| regression_wisard.memory_bytes())
+            + self.heads.memory_bytes()
+    }
+
+    /// Writes the whole model, canvas and WiSARD included, to a file
+    pub fn save<P: AsRef<path::Path>>(&self, model_file: P) -> io::Result<()> {
+        let payload = bincode::serialize(self).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        let mut file = io::BufWriter::new(fs::File::create(model_file)?);
+        file.write_all(MODEL_MAGIC)?;
+        file.write_all(&MODEL_VERSION.to_le_bytes())?;
+        file.write_all(&payload)?;
+        file.flush()
+    }
+
+    /// Writes the model to a temporary file next to the model file, then renames it over the model
+    /// file, so readers never see a partly written model
+    ///
+    /// The temporary file is named after the process and the save, so concurrent saves don't write
+    /// over each other, and the directory is synced after the rename so the new model survives a
+    /// crash.
+    pub fn save_atomically<P: AsRef<path::Path>>(&self, model_file: P) -> io::Result<()> {
+        let model_file = model_file.as_ref();
+        let mut temporary_name = model_file.file_name()
+                                           .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "DictaWav Error: Model file needs a file name!"))?
+                                           .to_os_string();
+        temporary_name.push(format!(".{}.{}.tmp", process::id(), TEMPORARY_SAVES.fetch_add(1usize, Ordering::Relaxed)));
+        let temporary_file = model_file.with_file_name(temporary_name);
+
+        let written = self.save(&temporary_file)
+                          .and_then(|_| fs::File::open(&temporary_file)?.sync_all())
+                          .and_then(|_| fs::rename(&temporary_file, model_file));
+        if written.is_err() {
+            let _ = fs::remove_file(&temporary_file);
+            return written;
+        }
+
+        DictaWav::sync_directory(model_file)
+    }
+
+    #[cfg(unix)]
+    fn sync_directory(model_file: &path::Path) -> io::Result<()> {
+        let directory = match model_file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => path::Path::new("."),
+        };
+        fs::File::open(directory)?.sync_all()
+    }
+
+    // Directories can't be opened as files to be synced elsewhere
+    #[cfg(not(unix))]
+    fn sync_directory(_model_file: &path::Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Reads a model written by `save`
+    pub fn load<P: AsRef<path::Path>>(model_file: P) -> io::Result<DictaWav> {
+        let mut bytes = Vec::new();
+        fs::File::open(model_file)?.read_to_end(&mut bytes)?;
+
+        let header_size = MODEL_MAGIC.len() + 4usize;
+        if bytes.len() < header_size || &bytes[..MODEL_MAGIC.len()] != MODEL_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "DictaWav Error: Not a DictaWav model file!"));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[MODEL_MAGIC.len()..header_size]);
+        if u32::from_le_bytes(version) != MODEL_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "DictaWav Error: Unsupported model file version!"));
+        }
+
+        let mut dictawav: DictaWav = bincode::deserialize(&bytes[header_size..])
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        dictawav.kernelcanvas.restore();
+        dictawav.wisard.restore();
+        dictawav.heads.restore();
+        Ok(dictawav)
+    }
+
+    /// Trains a file, along with its augmented copies when augmentation is on
     pub fn train<P: AsRef<path::Path>>(&mut self, wav_file: P, class_name: String) {
+        if self.trained_distance_metric.is_none() {
+            self.trained_distance_metric = Some(self.kernelcanvas.get_distance_metric());
+        }
+
+        for painted_canvas in self.read_and_process_training_file(wav_file) {
+            self.wisard.train(class_name.clone(), &painted_canvas);
+        }
+    }
+
+    /// Makes training, forgetting, corrections and splits use augmented copies of every file as
+    /// well, classification always using the file alone
+    ///
+    /// Copies of a file are the same every time it is trained or forgotten, so files trained with
+    /// an augmenter must be forgotten with the same one.
+    pub fn set_augmentation(&mut self, augmenter: Option<Augmenter>) {
+        self.augmenter = augmenter;
+    }
+
+    /// Trains the regression WiSARD to predict a continuous target, such as speaking rate or
+    /// speaker age, from the canvas painted for a file
+    pub fn train_target<P: AsRef<path::Path>>(&mut self, wav_file: P, target: f64) {
+        if self.trained_distance_metric.is_none() {
+            self.trained_distance_metric = Some(self.kernelcanvas.get_distance_metric());
+        }
+
+        let frames = self.read_wav_file_frames(wav_file);
+        let painted_canvas = self.paint_training_frames(frames);
+        let wisard = &self.wisard;
+        let aggregation = self.regression_aggregation;
+        self.regression_wisard
+            .get_or_insert_with(
+                || {
+                    let mut regression_wisard = wisard.untrained_regression_wisard();
+                    regression_wisard.set_aggregation(aggregation);
+                    regression_wisard
+                }
+            ).train(&painted_canvas, target);
+    }
+
+    /// Target the regression WiSARD predicts for a file, None when no ram recognises its canvas or
+    /// no target was trained
+    pub fn predict_target<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Option<f64> {
+        self.regression_wisard.as_ref()?;
+        let painted_canvas = self.read_and_process_wav_file(wav_file);
+        self.regression_wisard.as_ref().and_then(|regression_wisard| regression_wisard.predict(&painted_canvas))
+    }
+
+    /// Selects how the regression WiSARD combines the estimates of its rams, the default is
+    /// `Aggregation::Mean`
+    pub fn set_regression_aggregation(&mut self, aggregation: Aggregation) {
+        self.regression_aggregation = aggregation;
+        if let Some(ref mut regression_wisard) = self.regression_wisard {
+            regression_wisard.set_aggregation(aggregation);
+        }
+    }
+
+    /// Trains several heads from a single painted canvas, each one classifying its own label group
+    /// such as word, speaker or gender
+    ///
+    /// A head is created the first time it is named, with the WiSARD settings of the model at that
+    /// moment.
+    pub fn train_labels<P: AsRef<path::Path>>(&mut self, wav_file: P, labels: &[(&str, &str)]) {
+        if self.trained_distance_metric.is_none() {
+            self.trained_distance_metric = Some(self.kernelcanvas.get_distance_metric());
+        }
+
         let painted_canvas = self.read_and_process_wav_file(wav_file);
-        self.wisard.train(class_name, &painted_canvas);
+        self.heads.train(&painted_canvas, labels, &self.wisard);
     }
 
-    pub fn forget<P: AsRef<path::Path>>(&mut self, wav_file: P, class_name: &str) {
+    /// Prediction of every head for a file, by head name
+    pub fn predict_labels<P: AsRef<path::Path>>(&mut self, wav_file: P) -> BTreeMap<String, Prediction> {
         let painted_canvas = self.read_and_process_wav_file(wav_file);
-        self.wisard.forget(class_name, &painted_canvas);
+        self.heads.predict(&painted_canvas)
+    }
+
+    /// Names of the heads trained by `train_labels`, sorted
+    pub fn head_names(&self) -> Vec<String> {
+        self.heads.names()
+    }
+
+    /// Trains a coarse group, such as interjection or word, and the fine label within that group
+    pub fn train_hierarchical<P: AsRef<path::Path>>(&mut self, wav_file: P, group: &str, label: &str) {
+        if self.trained_distance_metric.is_none() {
+            self.trained_distance_metric = Some(self.kernelcanvas.get_distance_metric());
+        }
+
+        let painted_canvas = self.read_and_process_wav_file(wav_file);
+        self.heads.train_hierarchical(&painted_canvas, group, label, &self.wisard);
+    }
+
+    /// Classifies the group of a file, then its label among the labels of that group, None when
+    /// nothing was trained hierarchically
+    pub fn predict_hierarchical<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Option<HierarchicalPrediction> {
+        let painted_canvas = self.read_and_process_wav_file(wav_file);
+        self.heads.predict_hierarchical(&painted_canvas)
+    }
+
+    /// Selects how corrections are applied, kept and persisted, they aren't saved with the model
+    pub fn set_feedback_options(&mut self, feedback_options: FeedbackOptions) {
+        self.feedback_options = feedback_options;
+    }
+
+    /// Trains a wrongly predicted file with its actual class, forgetting it from the predicted one
+    /// when the options ask so and it was trained with it
+    ///
+    /// The correction is logged to be undone and returned, telling why the file couldn't be
+    /// forgotten from the predicted class if so. The model is only left unsaved when persisting it
+    /// fails.
+    pub fn correct<P: AsRef<path::Path>>(&mut self, wav_file: P, predicted: &str, actual: &str) -> Result<Correction, FeedbackError> {
+        if self.trained_distance_metric.is_none() {
+            self.trained_distance_metric = Some(self.kernelcanvas.get_distance_metric());
+        }
+
+        let painted_canvases = self.read_and_process_training_file(wav_file);
+        let predicted_forgetting = if self.feedback_options.forget_predicted {
+            Some(self.forget_canvases(predicted, &painted_canvases))
+        } else {
+            None
+        };
+        for painted_canvas in &painted_canvases {
+            self.wisard.train(String::from(actual), painted_canvas);
+        }
+
+        let correction = Correction {
+            predicted: String::from(predicted),
+            actual: String::from(actual),
+            predicted_forgetting,
+        };
+        self.correction_log.push(correction.clone(), painted_canvases, self.feedback_options.log_capacity);
+        self.persist_feedback()?;
+        Ok(correction)
+    }
+
+    /// Reverts the latest logged correction and returns it, None when there is none left
+    pub fn undo_correction(&mut self) -> Result<Option<Correction>, FeedbackError> {
+        let (correction, painted_canvases) = match self.correction_log.pop() {
+            Some(logged) => logged,
+            None => return Ok(None),
+        };
+
+        if let Err(error) = self.forget_canvases(&correction.actual, &painted_canvases) {
+            self.correction_log.restore(correction, painted_canvases);
+            return Err(FeedbackError::Undo(error));
+        }
+        if correction.forgot_predicted() {
+            for painted_canvas in &painted_canvases {
+                self.wisard.train(correction.predicted.clone(), painted_canvas);
+            }
+        }
+
+        self.persist_feedback()?;
+        Ok(Some(correction))
+    }
+
+    /// Corrections that can be undone, from the oldest to the latest
+    pub fn corrections(&self) -> Vec<Correction> {
+        self.correction_log.corrections()
+    }
+
+    /// Removes a trained file from a class, failing without changes when the class is unknown or
+    /// wasn't trained with it
+    ///
+    /// With augmentation on, the augmented copies are forgotten as well, and a copy that wasn't
+    /// trained leaves the file and every copy trained.
+    pub fn forget<P: AsRef<path::Path>>(&mut self, wav_file: P, class_name: &str) -> Result<(), ForgetError> {
+        let painted_canvases = self.read_and_process_training_file(wav_file);
+        self.forget_canvases(class_name, &painted_canvases)
+    }
+
+    /// Forgets every canvas from a class, training the ones already forgotten again when one of
+    /// them fails
+    fn forget_canvases(&mut self, class_name: &str, painted_canvases: &[Vec<bool>]) -> Result<(), ForgetError> {
+        for (index, painted_canvas) in painted_canvases.iter().enumerate() {
+            if let Err(error) = self.wisard.forget(class_name, painted_canvas) {
+                for forgotten_canvas in &painted_canvases[..index] {
+                    self.wisard.train(String::from(class_name), forgotten_canvas);
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes a class and everything it learned, returns whether it existed
+    pub fn remove_class(&mut self, class_name: &str) -> bool {
+        self.wisard.remove_class(class_name)
+    }
+
+    /// Every class with its trained minus forgotten examples, sorted by class name
+    ///
+    /// An example is a painted canvas, so with augmentation on every augmented copy counts as well
+    /// as the file itself.
+    pub fn classes(&self) -> Vec<(String, u64)> {
+        self.wisard.class_examples()
+    }
+
+    /// Gives a class a new name, keeping everything it learned
+    pub fn rename_class(&mut self, class_name: &str, new_class_name: &str) -> Result<(), VocabularyError> {
+        self.wisard.rename_class(class_name, new_class_name)
+    }
+
+    /// Adds what a class learned to another one and deletes it
+    ///
+    /// Non cumulative rams store an address when either class stored it, whatever the mode.
+    /// Cumulative ones keep the larger count with `RamMerge::Or`, counting the examples of the
+    /// larger class, and add the counts with `RamMerge::Sum`, so only a sum can still forget every
+    /// file of both classes. Clustered classes keep the merged discriminators apart up to the
+    /// clustering limit.
+    pub fn merge_classes(&mut self, class_name: &str, into_class_name: &str, mode: RamMerge) -> Result<(), VocabularyError> {
+        self.wisard.merge_classes(class_name, into_class_name, mode)
+    }
+
+    /// Replaces a class with the classes of the given files, trained from scratch with them
+    ///
+    /// A new class may keep the name of the split one, but not take the name of another class,
+    /// and at least one file must be given. Every file is painted before the model changes.
+    pub fn split_class<P, I>(&mut self, class_name: &str, samples: I) -> Result<(), VocabularyError>
+        where P: AsRef<path::Path>, I: IntoIterator<Item=(P, String)>
+    {
+        if !self.wisard.has_class(class_name) {
+            return Err(VocabularyError::UnknownClass(String::from(class_name)));
+        }
+        let samples: Vec<(P, String)> = samples.into_iter().collect();
+        if samples.is_empty() {
+            return Err(VocabularyError::NoSamples(String::from(class_name)));
+        }
+        if let Some((_, taken_name)) = samples.iter().find(|&(_, new_class_name)| new_class_name != class_name && self.wisard.has_class(new_class_name)) {
+            return Err(VocabularyError::ClassExists(taken_name.clone()));
+        }
+
+        let painted_samples: Vec<(Vec<Vec<bool>>, String)> = samples.into_iter()
+                                                                    .map(|(wav_file, new_class_name)| (self.read_and_process_training_file(wav_file), new_class_name))
+                                                                    .collect();
+        self.wisard.remove_class(class_name);
+        for (painted_canvases, new_class_name) in painted_samples {
+            for painted_canvas in &painted_canvases {
+                self.wisard.train(new_class_name.clone(), painted_canvas);
+            }
+        }
+        Ok(())
     }
 
     pub fn classify<P: AsRef<path::Path>>(&mut self, wav_file: P) -> String {
@@ -72,12 +504,317 @@ impl DictaWav {
         self.wisard.classification_confidence_and_probability(&painted_canvas)
     }
 
-    fn read_and_process_wav_file<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Vec<bool> {
+    /// Classifies a file, reporting confidence and the classes tied for the highest probability
+    pub fn predict<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Prediction {
+        let painted_canvas = self.read_and_process_wav_file(wav_file);
+        self.wisard.predict(&painted_canvas)
+    }
+
+    /// Classifies mono samples in [-1, 1] instead of a file, such as a recording corrupted on purpose
+    pub fn predict_audio(&mut self, audio_data: Vec<f64>, sample_rate: usize) -> Prediction {
+        self.check_distance_metric();
+        let frames = self.audio_data_frames(audio_data, sample_rate);
+        self.kernelcanvas.process(frames);
+        let painted_canvas = self.kernelcanvas.get_painted_canvas();
+        self.wisard.predict(&painted_canvas)
+    }
+
+    /// Classifies a file and reports the ram votes of every class and the canvas bits behind them
+    pub fn explain<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Explanation {
+        let painted_canvas = self.read_and_process_wav_file(wav_file);
+        Explanation::new(
+            self.wisard.explain(&painted_canvas),
+            &painted_canvas,
+            self.kernelcanvas.get_kernel_count(),
+            self.kernelcanvas.get_time_segments(),
+        )
+    }
+
+    /// Replaces the canvas kernels, learning them from the frames of the given training files
+    ///
+    /// Frames are normalised with the current settings, so global or per speaker statistics must be
+    /// fitted before. Kernel activation counts are reset. It must be done before training.
+    pub fn learn_kernels<P, I>(&mut self, wav_files: I, learning: KernelLearning)
+        where P: AsRef<path::Path>, I: IntoIterator<Item=P>
+    {
+        self.check_untrained("Learning kernels");
+        let mut training_frames = Vec::new();
+        if learning != KernelLearning::Random {
+            for wav_file in wav_files {
+                let frames = self.read_wav_file_frames(wav_file);
+                self.kernelcanvas.process(frames);
+                training_frames.extend(self.kernelcanvas.get_processed_frames().iter().cloned());
+            }
+        }
+
+        self.kernelcanvas.learn_kernels(&training_frames, learning);
+    }
+
+    /// Selects the distance between frames and kernels
+    ///
+    /// The WiSARD only makes sense with the metric it was trained with, so it must be selected
+    /// before training. `DistanceMetric::Mahalanobis` needs `fit_covariance` to be called first.
+    pub fn set_distance_metric(&mut self, distance_metric: DistanceMetric) {
+        self.check_untrained("Changing the distance metric");
+        self.kernelcanvas.set_distance_metric(distance_metric);
+    }
+
+    /// Learns the covariance of processed training frames used by `DistanceMetric::Mahalanobis`,
+    /// before training
+    pub fn fit_covariance<P, I>(&mut self, wav_files: I)
+        where P: AsRef<path::Path>, I: IntoIterator<Item=P>
+    {
+        self.check_untrained("Fitting the covariance");
+        let mut training_frames = Vec::new();
+        for wav_file in wav_files {
+            let frames = self.read_wav_file_frames(wav_file);
+            self.kernelcanvas.process(frames);
+            training_frames.extend(self.kernelcanvas.get_processed_frames().iter().cloned());
+        }
+
+        self.kernelcanvas.fit_covariance(&training_frames);
+    }
+
+    /// Writes the canvas kernels so other models or tools can use the same feature space
+    pub fn export_kernels<P: AsRef<path::Path>>(&self, kernels_file: P, format: KernelFormat) -> io::Result<()> {
+        self.kernelcanvas.export_kernels(io::BufWriter::new(fs::File::create(kernels_file)?), format)
+    }
+
+    /// Replaces the canvas kernels with exported ones, which must match the kernels count and the
+    /// dimension of the processed frames, before training
+    pub fn import_kernels<P: AsRef<path::Path>>(&mut self, kernels_file: P, format: KernelFormat) -> io::Result<()> {
+        self.check_untrained("Importing kernels");
+        self.kernelcanvas.import_kernels(fs::File::open(kernels_file)?, format)
+    }
+
+    /// Replaces the canvas kernels with the given coordinates, before training
+    pub fn set_kernels(&mut self, kernels: Vec<Vec<f64>>) -> Result<(), KernelSetError> {
+        self.check_untrained("Setting kernels");
+        self.kernelcanvas.set_kernels(kernels)
+    }
+
+    /// Image of the canvas painted by a file, one tile of kernels per time segment and output block
+    pub fn canvas_image<P: AsRef<path::Path>>(&mut self, wav_file: P) -> GrayImage {
+        let painted_canvas: Vec<f64> = self.read_and_process_wav_file(wav_file)
+                                           .into_iter()
+                                           .map(|bit| if bit { 1f64 } else { 0f64 })
+                                           .collect();
+        self.retina_image(&painted_canvas)
+    }
+
+    /// Heatmaps of how often each canvas bit was set in what every class stored, laid out as
+    /// `canvas_image`, sorted by class name
+    pub fn class_heatmaps(&self) -> Vec<(String, GrayImage)> {
+        self.wisard
+            .class_retina_hit_counts()
+            .into_iter()
+            .map(
+                |(class_name, hit_counts)| {
+                    let hit_counts: Vec<f64> = hit_counts.into_iter().map(|count| count as f64).collect();
+                    (class_name, self.retina_image(&hit_counts))
+                }
+            ).collect()
+    }
+
+    /// Writes the heatmap of every class to `<class name>.pgm` inside a directory
+    pub fn save_class_heatmaps<P: AsRef<path::Path>>(&self, directory: P) -> io::Result<()> {
+        fs::create_dir_all(&directory)?;
+        for (class_name, image) in self.class_heatmaps() {
+            image.save_pgm(directory.as_ref().join(format!("{}.pgm", class_name)))?;
+        }
+        Ok(())
+    }
+
+    /// Spectrogram like image of the features the front-end extracts from a file
+    pub fn features_image<P: AsRef<path::Path>>(&self, wav_file: P) -> GrayImage {
+        diagnostics::features_image(&self.read_wav_file_frames(wav_file))
+    }
+
+    /// Selects which kernels each frame activates, before training
+    pub fn set_painting_mode(&mut self, painting_mode: PaintingMode) {
+        self.check_untrained("Changing the painting mode");
+        self.kernelcanvas.set_painting_mode(painting_mode);
+    }
+
+    /// Selects how kernel hits are encoded into the retina, which keeps the same size, before
+    /// training
+    pub fn set_canvas_encoding(&mut self, encoding: CanvasEncoding) {
+        self.check_untrained("Changing the canvas encoding");
+        self.kernelcanvas.set_encoding(encoding);
+    }
+
+    /// Learns the per-kernel thresholds of `CanvasEncoding::DistanceThresholds` over training files,
+    /// before training
+    pub fn fit_distance_thresholds<P, I>(&mut self, wav_files: I)
+        where P: AsRef<path::Path>, I: IntoIterator<Item=P>
+    {
+        self.check_untrained("Fitting distance thresholds");
+        let mut nearest_distances = Vec::new();
+        for wav_file in wav_files {
+            let frames = self.read_wav_file_frames(wav_file);
+            self.kernelcanvas.process(frames);
+            nearest_distances.push(self.kernelcanvas.utterance_nearest_distances());
+        }
+
+        self.kernelcanvas.fit_distance_thresholds(&nearest_distances);
+    }
+
+    /// Selects the structure used to find the nearest kernel of each frame
+    pub fn set_nearest_kernel_search(&mut self, search: NearestKernelSearch) {
+        self.kernelcanvas.set_nearest_kernel_search(search);
+    }
+
+    /// How many kernels were hit by at least one frame of the training files since kernels were
+    /// last set, as its nearest kernel or one of those the painting mode also activates
+    ///
+    /// Files that are only classified or predicted don't count.
+    pub fn activated_kernels_count(&self) -> usize {
+        self.kernelcanvas.activated_kernels_count()
+    }
+
+    pub fn kernels_count(&self) -> usize {
+        self.kernelcanvas.get_kernel_count()
+    }
+
+    /// Selects how features are normalised before painting the canvas, before training
+    pub fn set_normalization(&mut self, normalization: Normalization) {
+        self.check_untrained("Changing the normalization");
+        self.kernelcanvas.set_normalization(normalization);
+    }
+
+    /// Selects the nonlinearity applied after normalisation, before training
+    pub fn set_nonlinearity(&mut self, nonlinearity: Nonlinearity) {
+        self.check_untrained("Changing the nonlinearity");
+        self.kernelcanvas.set_nonlinearity(nonlinearity);
+    }
+
+    /// Selects the speaker whose statistics are used with `Normalization::PerSpeaker`
+    pub fn set_current_speaker(&mut self, speaker: Option<String>) {
+        self.kernelcanvas.set_current_speaker(speaker);
+    }
+
+    /// Learns global normalisation statistics over the given training files, before training
+    pub fn fit_global_normalization<P, I>(&mut self, wav_files: I) -> CmvnStatistics
+        where P: AsRef<path::Path>, I: IntoIterator<Item=P>
+    {
+        self.check_untrained("Fitting the global normalization");
+        let statistics = self.accumulate_statistics(wav_files);
+        self.kernelcanvas.set_global_statistics(statistics.clone());
+        statistics
+    }
+
+    /// Learns normalisation statistics over the given files of a single speaker, before training
+    pub fn fit_speaker_normalization<P, I>(&mut self, speaker: &str, wav_files: I) -> CmvnStatistics
+        where P: AsRef<path::Path>, I: IntoIterator<Item=P>
+    {
+        self.check_untrained("Fitting a speaker normalization");
+        let statistics = self.accumulate_statistics(wav_files);
+        self.kernelcanvas.set_speaker_statistics(String::from(speaker), statistics.clone());
+        statistics
+    }
+
+    fn retina_image(&self, values: &[f64]) -> GrayImage {
+        diagnostics::retina_image(
+            values,
+            self.kernelcanvas.get_kernel_count(),
+            self.kernelcanvas.get_time_segments(),
+            self.kernelcanvas.get_output_factor(),
+        )
+    }
+
+    fn persist_feedback(&self) -> Result<(), FeedbackError> {
+        match self.feedback_options.model_file {
+            Some(ref model_file) => self.save_atomically(model_file).map_err(FeedbackError::Persist),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics once anything was trained, the change giving another feature space than the one the
+    /// WiSARDs learned
+    fn check_untrained(&self, change: &str) {
+        if self.trained_distance_metric.is_some() {
+            panic!("DictaWav Error: {} must be done before training!", change);
+        }
+    }
+
+    fn check_distance_metric(&self) {
+        if let Some(trained_distance_metric) = self.trained_distance_metric {
+            if trained_distance_metric != self.kernelcanvas.get_distance_metric() {
+                panic!("DictaWav Error: WiSARD was trained with the {:?} distance metric!", trained_distance_metric);
+            }
+        }
+    }
+
+    fn accumulate_statistics<P, I>(&mut self, wav_files: I) -> CmvnStatistics
+        where P: AsRef<path::Path>, I: IntoIterator<Item=P>
+    {
+        let mut accumulator = CmvnAccumulator::new();
+        for wav_file in wav_files {
+            let frames = self.read_wav_file_frames(wav_file);
+            self.kernelcanvas.accumulate_statistics(frames, &mut accumulator);
+        }
+
+        if accumulator.frames_count() == 0usize {
+            panic!("DictaWav Error: Trying to learn normalization statistics without any frame!");
+        }
+        accumulator.finish()
+    }
+
+    fn read_wav_file_frames<P: AsRef<path::Path>>(&self, wav_file: P) -> Vec<Vec<f64>> {
         let wav_handler = WavHandler::new(wav_file).unwrap();
-        let mut preprocessor = PreProcessor::new(wav_handler.get_sample_rate() as usize);
-        preprocessor.process(wav_handler.extract_audio_data());
+        let sample_rate = wav_handler.get_sample_rate() as usize;
+        self.audio_data_frames(wav_handler.extract_audio_data(), sample_rate)
+    }
+
+    fn audio_data_frames(&self, audio_data: Vec<f64>, sample_rate: usize) -> Vec<Vec<f64>> {
+        let mut preprocessor = PreProcessor::new(sample_rate, &self.front_end);
+        preprocessor.process(audio_data);
+
+        preprocessor.extract_processed_frames()
+    }
+
+    /// Canvases of a file and of its augmented copies, the copies keyed by the audio samples so a
+    /// file gets the same copies whatever path it is read from
+    ///
+    /// Their kernel hits count as activations, unlike those of files that are only classified.
+    fn read_and_process_training_file<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Vec<Vec<bool>> {
+        let augmenter = match self.augmenter {
+            Some(ref augmenter) => augmenter.clone(),
+            None => {
+                let frames = self.read_wav_file_frames(wav_file);
+                return vec![self.paint_training_frames(frames)];
+            }
+        };
+        self.check_distance_metric();
+
+        let wav_handler = WavHandler::new(wav_file).unwrap();
+        let sample_rate = wav_handler.get_sample_rate() as usize;
+        let audio_data = wav_handler.extract_audio_data();
+        // FNV-1a, stable across runs unlike the standard hasher
+        let key = audio_data.iter()
+                            .flat_map(|sample| sample.to_bits().to_le_bytes())
+                            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3u64));
+
+        let mut recordings = augmenter.augment(&audio_data, sample_rate, key);
+        recordings.insert(0usize, audio_data);
+        recordings.into_iter().map(
+            |recording| {
+                let frames = self.audio_data_frames(recording, sample_rate);
+                self.paint_training_frames(frames)
+            }
+        ).collect()
+    }
+
+    fn paint_training_frames(&mut self, frames: Vec<Vec<f64>>) -> Vec<bool> {
+        self.check_distance_metric();
+        self.kernelcanvas.process(frames);
+        self.kernelcanvas.get_training_canvas()
+    }
 
-        self.kernelcanvas.process(preprocessor.extract_processed_frames());
+    fn read_and_process_wav_file<P: AsRef<path::Path>>(&mut self, wav_file: P) -> Vec<bool> {
+        self.check_distance_metric();
+        let frames = self.read_wav_file_frames(wav_file);
+        self.kernelcanvas.process(frames);
         self.kernelcanvas.get_painted_canvas()
     }
 }
@@ -85,8 +822,294 @@ impl DictaWav {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::env;
+
+    fn small_model() -> DictaWav {
+        DictaWav::new(FrontEnd::mfcc(), 64usize, 2usize, 1usize, 128usize, 8usize, true, 0.002f64, 1u64, true, true)
+    }
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    #[should_panic(expected = "WiSARD retina size must be kernels count * time segments * output factor")]
+    fn retina_size_must_match_the_segmented_canvas() {
+        DictaWav::new(FrontEnd::mfcc(), 64usize, 2usize, 2usize, 128usize, 8usize, true, 0.002f64, 1u64, true, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Learning kernels must be done before training")]
+    fn kernels_cant_change_after_training() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.learn_kernels(["dataset/a/1.wav"], KernelLearning::KMeans { iterations: 1usize, seed: 1usize });
+    }
+
+    #[test]
+    fn saved_model_classifies_the_same() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.train("dataset/ah!/1.wav", String::from("ah!"));
+
+        let model_file = env::temp_dir().join(format!("dictawav-test-{}.model", process::id()));
+        dictawav.save(&model_file).unwrap();
+        let mut loaded = DictaWav::load(&model_file).unwrap();
+        fs::remove_file(&model_file).unwrap();
+
+        for wav_file in &["dataset/a/2.wav", "dataset/ah!/2.wav"] {
+            assert_eq!(
+                loaded.classification_and_probability(wav_file),
+                dictawav.classification_and_probability(wav_file)
+            );
+        }
+    }
+
+    #[test]
+    fn heatmap_of_a_single_example_is_its_canvas() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+
+        let heatmaps = dictawav.class_heatmaps();
+        assert_eq!(heatmaps.len(), 1usize);
+        assert_eq!(heatmaps[0].1, dictawav.canvas_image("dataset/a/1.wav"));
+    }
+
+    #[test]
+    fn explanation_agrees_with_classification() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.train("dataset/ah!/1.wav", String::from("ah!"));
+
+        let explanation = dictawav.explain("dataset/a/1.wav");
+        assert_eq!(explanation.classification, dictawav.classify("dataset/a/1.wav"));
+        assert_eq!(explanation.classes.len(), 2usize);
+        assert_eq!(explanation.classes[0].class_name, "a");
+
+        // A trained example is fully recognised by its own class
+        let own_class = &explanation.classes[0];
+        assert_eq!(own_class.votes.len(), own_class.ram_values.len());
+        assert!(own_class.votes.iter().all(|vote| vote.value > explanation.bleaching_threshold));
+        assert!(!explanation.contributing_kernels("a").is_empty());
+    }
+
+    #[test]
+    fn statistics_count_examples_and_addresses() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.train("dataset/a/2.wav", String::from("a"));
+        dictawav.train("dataset/ah!/1.wav", String::from("ah!"));
+        dictawav.forget("dataset/a/2.wav", "a").unwrap();
+
+        let statistics = dictawav.statistics();
+        assert_eq!(statistics.wisard.trained_examples, 2u64);
+        assert_eq!(statistics.wisard.rams_count, 32usize);
+
+        // One example leaves exactly one address with a counter of one in every ram
+        for class in &statistics.wisard.classes {
+            assert_eq!(class.trained_examples, 1u64);
+            assert!(class.occupied_addresses.iter().all(|occupied| *occupied == 1usize));
+            assert_eq!(class.counter_histogram.get(&1u64), Some(&16usize));
+        }
+        assert!(statistics.activated_kernels_fraction() > 0f64);
+    }
+
+    #[test]
+    fn classifying_leaves_kernel_activations_alone() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        let activated_kernels_count = dictawav.activated_kernels_count();
+
+        for wav_file in &["dataset/a/2.wav", "dataset/ah!/1.wav", "dataset/ah!/2.wav"] {
+            dictawav.classify(wav_file);
+            dictawav.predict(wav_file);
+        }
+        assert_eq!(dictawav.activated_kernels_count(), activated_kernels_count);
+
+        dictawav.train("dataset/ah!/1.wav", String::from("ah!"));
+        assert!(dictawav.activated_kernels_count() >= activated_kernels_count);
+    }
+
+    #[test]
+    fn forgetting_untrained_samples_fails() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+
+        assert_eq!(dictawav.forget("dataset/a/1.wav", "ah!"), Err(ForgetError::UnknownClass(String::from("ah!"))));
+        assert_eq!(dictawav.forget("dataset/a/2.wav", "a"), Err(ForgetError::UntrainedSample(String::from("a"))));
+        assert_eq!(dictawav.forget("dataset/a/1.wav", "a"), Ok(()));
+        assert_eq!(dictawav.forget("dataset/a/1.wav", "a"), Err(ForgetError::NoTrainedExamples(String::from("a"))));
+        assert_eq!(dictawav.statistics().wisard.occupied_addresses, 0usize);
+
+        assert!(dictawav.remove_class("a"));
+        assert!(!dictawav.remove_class("a"));
+        assert!(dictawav.statistics().wisard.classes.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Changing the distance metric must be done before training")]
+    fn changing_metric_after_training_panics() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.set_distance_metric(DistanceMetric::Manhattan);
+    }
+
+    #[test]
+    #[should_panic(expected = "Changing the canvas encoding must be done before training")]
+    fn painting_cant_change_after_training() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.set_canvas_encoding(CanvasEncoding::HitThermometer { step: 1u64 });
+    }
+
+    #[test]
+    fn regression_recalls_trained_targets() {
+        let mut dictawav = small_model();
+        let classification_memory = dictawav.memory_bytes();
+        assert_eq!(dictawav.predict_target("dataset/a/1.wav"), None);
+
+        dictawav.train_target("dataset/a/1.wav", 1.5f64);
+        assert!(dictawav.memory_bytes() > classification_memory);
+        dictawav.train_target("dataset/ah!/1.wav", 1.5f64);
+
+        // Every ram recognising a canvas only saw the same target
+        assert_eq!(dictawav.predict_target("dataset/a/1.wav"), Some(1.5f64));
+        dictawav.set_regression_aggregation(Aggregation::Median);
+        assert_eq!(dictawav.predict_target("dataset/ah!/1.wav"), Some(1.5f64));
+    }
+
+    #[test]
+    fn heads_and_hierarchy_classify_the_same_canvas() {
+        let mut dictawav = small_model();
+        dictawav.train_labels("dataset/a/1.wav", &[("word", "a"), ("kind", "vowel")]);
+        dictawav.train_labels("dataset/ah!/1.wav", &[("word", "ah!"), ("kind", "interjection")]);
+        assert_eq!(dictawav.head_names(), vec![String::from("kind"), String::from("word")]);
+
+        let predictions = dictawav.predict_labels("dataset/a/1.wav");
+        assert_eq!(predictions["word"].class_name, "a");
+        assert_eq!(predictions["kind"].class_name, "vowel");
+
+        assert_eq!(dictawav.predict_hierarchical("dataset/a/1.wav"), None);
+        dictawav.train_hierarchical("dataset/a/1.wav", "vowel", "a");
+        dictawav.train_hierarchical("dataset/a/2.wav", "vowel", "a");
+        dictawav.train_hierarchical("dataset/ah!/1.wav", "interjection", "ah!");
+
+        let prediction = dictawav.predict_hierarchical("dataset/ah!/1.wav").unwrap();
+        assert_eq!(prediction.group.class_name, "interjection");
+        assert_eq!(prediction.label.unwrap().class_name, "ah!");
+    }
+
+    #[test]
+    fn same_seed_gives_same_model() {
+        let mut first = small_model();
+        let mut second = small_model();
+        first.set_seed(5usize);
+        second.set_seed(5usize);
+        first.train("dataset/a/1.wav", String::from("a"));
+        second.train("dataset/a/1.wav", String::from("a"));
+
+        assert_eq!(first.statistics(), second.statistics());
+        assert_eq!(first.canvas_image("dataset/a/2.wav"), second.canvas_image("dataset/a/2.wav"));
+
+        for dictawav in [&mut first, &mut second] {
+            dictawav.train_target("dataset/a/1.wav", 1f64);
+            dictawav.train_target("dataset/ah!/1.wav", 3f64);
+        }
+        assert_eq!(first.predict_target("dataset/a/2.wav"), second.predict_target("dataset/a/2.wav"));
+    }
+
+    #[test]
+    fn corrections_are_persisted_and_undone() {
+        let model_file = env::temp_dir().join(format!("dictawav-corrections-test-{}.model", process::id()));
+
+        let mut dictawav = small_model();
+        dictawav.set_feedback_options(FeedbackOptions {
+            forget_predicted: true,
+            log_capacity: 1usize,
+            model_file: Some(model_file.clone()),
+        });
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.train("dataset/ah!/1.wav", String::from("ah!"));
+        let before = dictawav.statistics().wisard;
+
+        let untrained = dictawav.correct("dataset/ah!/2.wav", "a", "ah!").unwrap();
+        assert_eq!(untrained.predicted_forgetting, Some(Err(ForgetError::UntrainedSample(String::from("a")))));
+        let unknown = dictawav.correct("dataset/ah!/2.wav", "b", "ah!").unwrap();
+        assert_eq!(unknown.predicted_forgetting, Some(Err(ForgetError::UnknownClass(String::from("b")))));
+        dictawav.undo_correction().unwrap();
+        dictawav.correct("dataset/a/1.wav", "a", "ah!").unwrap();
+        let corrections = dictawav.corrections();
+        assert_eq!(corrections.len(), 1usize);
+        assert!(corrections[0].forgot_predicted());
+
+        let saved = DictaWav::load(&model_file).unwrap();
+        assert_eq!(saved.statistics(), dictawav.statistics());
+
+        assert_eq!(dictawav.undo_correction().unwrap(), Some(corrections[0].clone()));
+        assert_eq!(dictawav.undo_correction().unwrap(), None);
+        let after = dictawav.statistics().wisard;
+        assert_eq!(after.trained_examples, before.trained_examples + 1u64);
+        assert_eq!(after.classes[0], before.classes[0]);
+
+        fs::remove_file(&model_file).unwrap();
+        let temporary_files = fs::read_dir(env::temp_dir()).unwrap()
+                                                           .filter_map(|entry| entry.ok())
+                                                           .filter(|entry| entry.file_name().to_string_lossy().starts_with(&*model_file.file_name().unwrap().to_string_lossy()))
+                                                           .count();
+        assert_eq!(temporary_files, 0usize);
+    }
+
+    #[test]
+    fn splitting_retrains_the_given_files() {
+        let mut dictawav = small_model();
+        dictawav.train("dataset/a/1.wav", String::from("vowel"));
+        dictawav.train("dataset/ah!/1.wav", String::from("vowel"));
+        dictawav.train("dataset/ah!/2.wav", String::from("ah!"));
+
+        let nothing: Vec<(&str, String)> = Vec::new();
+        assert_eq!(dictawav.split_class("vowel", nothing), Err(VocabularyError::NoSamples(String::from("vowel"))));
+        let taken = dictawav.split_class("vowel", vec![("dataset/a/1.wav", String::from("ah!"))]);
+        assert_eq!(taken, Err(VocabularyError::ClassExists(String::from("ah!"))));
+
+        dictawav.split_class("vowel", vec![
+            ("dataset/a/1.wav", String::from("a")),
+            ("dataset/a/2.wav", String::from("a")),
+        ]).unwrap();
+        assert_eq!(dictawav.classes(), vec![(String::from("a"), 2u64), (String::from("ah!"), 1u64)]);
+        assert_eq!(dictawav.classify("dataset/a/1.wav"), "a");
+    }
+
+    #[test]
+    fn augmented_copies_are_trained_and_forgotten() {
+        let mut dictawav = small_model();
+        dictawav.set_augmentation(Some(Augmenter::new(vec![
+            Augmentation::Gain { min_db: -3f64, max_db: 3f64 },
+            Augmentation::Noise { color: NoiseColor::White, min_snr_db: 15f64, max_snr_db: 25f64 },
+        ], 2usize, 3usize)));
+        dictawav.train("dataset/a/1.wav", String::from("a"));
+        dictawav.train("dataset/ah!/1.wav", String::from("ah!"));
+        assert_eq!(dictawav.classes(), vec![(String::from("a"), 3u64), (String::from("ah!"), 3u64)]);
+        let trained = dictawav.statistics();
+
+        // Copies drawn by another augmenter weren't trained, so nothing is forgotten
+        let augmenter = dictawav.augmenter.take();
+        dictawav.set_augmentation(Some(Augmenter::new(vec![
+            Augmentation::Gain { min_db: -3f64, max_db: 3f64 },
+        ], 2usize, 4usize)));
+        assert!(dictawav.forget("dataset/a/1.wav", "a").is_err());
+        assert_eq!(dictawav.statistics(), trained);
+        dictawav.set_augmentation(augmenter);
+
+        dictawav.correct("dataset/ah!/1.wav", "ah!", "a").unwrap();
+        assert_eq!(dictawav.classes(), vec![(String::from("a"), 6u64), (String::from("ah!"), 0u64)]);
+        dictawav.undo_correction().unwrap();
+        assert_eq!(dictawav.statistics(), trained);
+
+        // The copies follow the audio, not the path it is read from
+        dictawav.forget("dataset/a/../a/1.wav", "a").unwrap();
+        assert_eq!(dictawav.classes(), vec![(String::from("a"), 0u64), (String::from("ah!"), 3u64)]);
+        assert_eq!(dictawav.statistics().wisard.classes[0].occupied_addresses.iter().sum::<usize>(), 0usize);
+    }
 }
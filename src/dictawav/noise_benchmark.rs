@@ -0,0 +1,252 @@
+extern crate rand;
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use self::rand::{Rng, SeedableRng, StdRng};
+use super::augmentation::{self, NoiseColor};
+use super::wav_handler::WavHandler;
+
+/// Noise corrupting the tested recordings, all of it made locally
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BenchmarkNoise {
+    White,
+    Pink,
+    /// Recordings of other classes mixed together, each one as loud as the others
+    Babble { talkers: usize },
+}
+
+impl fmt::Display for BenchmarkNoise {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BenchmarkNoise::White => write!(formatter, "white"),
+            BenchmarkNoise::Pink => write!(formatter, "pink"),
+            BenchmarkNoise::Babble { talkers } => write!(formatter, "babble{}", talkers),
+        }
+    }
+}
+
+/// Right and total answers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Score {
+    right: usize,
+    total: usize,
+}
+
+impl Score {
+    fn add(&mut self, is_right: bool) {
+        self.total += 1usize;
+        if is_right {
+            self.right += 1usize;
+        }
+    }
+
+    fn accuracy(&self) -> f64 {
+        if self.total == 0usize { 0f64 } else { self.right as f64 / self.total as f64 }
+    }
+}
+
+/// Accuracy of a classifier on recordings corrupted by every noise at every signal to noise ratio
+pub struct NoiseBenchmark {
+    noises: Vec<BenchmarkNoise>,
+    snrs_db: Vec<f64>,
+    /// Recordings babble is mixed from, scaled to unit power, with their classes
+    babble_recordings: Vec<(Vec<f64>, String)>,
+    random_generator: StdRng,
+    clean: Score,
+    /// By noise, then by ratio
+    scores: Vec<Vec<Score>>,
+}
+
+impl NoiseBenchmark {
+    /// Reads the babble files once when some noise is babble, silent ones being left out
+    pub fn new(noises: Vec<BenchmarkNoise>, snrs_db: Vec<f64>, babble_files: Vec<(PathBuf, String)>, seed: usize) -> io::Result<NoiseBenchmark> {
+        let scores = vec![vec![Score::default(); snrs_db.len()]; noises.len()];
+
+        let mut babble_recordings = Vec::new();
+        if noises.iter().any(|noise| matches!(noise, BenchmarkNoise::Babble { .. })) {
+            for (babble_file, class_name) in babble_files {
+                let (talker, _) = read_samples(babble_file)?;
+                let talker_power = augmentation::power(&talker);
+                if talker.is_empty() || talker_power == 0f64 {
+                    continue;
+                }
+                let scale = 1f64 / talker_power.sqrt();
+                babble_recordings.push((talker.into_iter().map(|sample| sample * scale).collect(), class_name));
+            }
+        }
+
+        Ok(NoiseBenchmark {
+            noises,
+            snrs_db,
+            babble_recordings,
+            random_generator: SeedableRng::from_seed(&[seed][..]),
+            clean: Score::default(),
+            scores,
+        })
+    }
+
+    /// Classifies a file clean and with every noise, `classify` answering for mono samples and
+    /// their sample rate
+    ///
+    /// Babble for a file is only mixed from files of other classes.
+    pub fn evaluate<P, F>(&mut self, wav_file: P, class_name: &str, mut classify: F) -> io::Result<()>
+        where P: AsRef<Path>, F: FnMut(&[f64], usize) -> String
+    {
+        let (samples, sample_rate) = read_samples(wav_file)?;
+        self.clean.add(classify(&samples, sample_rate) == class_name);
+
+        for noise_index in 0..self.noises.len() {
+            let noise = match self.noises[noise_index] {
+                BenchmarkNoise::White => augmentation::noise(samples.len(), NoiseColor::White, &mut self.random_generator),
+                BenchmarkNoise::Pink => augmentation::noise(samples.len(), NoiseColor::Pink, &mut self.random_generator),
+                BenchmarkNoise::Babble { talkers } => self.babble(samples.len(), class_name, talkers)?,
+            };
+
+            for snr_index in 0..self.snrs_db.len() {
+                let corrupted = augmentation::mix_at_snr(&samples, &noise, self.snrs_db[snr_index]);
+                self.scores[noise_index][snr_index].add(classify(&corrupted, sample_rate) == class_name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clean_accuracy(&self) -> f64 {
+        self.clean.accuracy()
+    }
+
+    /// Accuracy with the noise and ratio at those indices of the lists given
+    pub fn accuracy(&self, noise_index: usize, snr_index: usize) -> f64 {
+        self.scores[noise_index][snr_index].accuracy()
+    }
+
+    /// Accuracy in percent of every noise, in columns, at every ratio, in rows
+    pub fn table(&self) -> String {
+        let mut table = format!("{:>8}", "SNR (dB)");
+        for noise in &self.noises {
+            table.push_str(&format!(" {:>10}", noise.to_string()));
+        }
+        table.push_str(&format!("\n{:>8}", "clean"));
+        for _ in &self.noises {
+            table.push_str(&format!(" {:>9.2}%", self.clean_accuracy() * 100f64));
+        }
+
+        for (snr_index, snr_db) in self.snrs_db.iter().enumerate() {
+            table.push_str(&format!("\n{:>8}", snr_db));
+            for noise_index in 0..self.noises.len() {
+                table.push_str(&format!(" {:>9.2}%", self.accuracy(noise_index, snr_index) * 100f64));
+            }
+        }
+        table
+    }
+
+    /// One line per noise and ratio, the clean accuracy having no ratio
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "noise,snr_db,accuracy,right,total")?;
+        writeln!(writer, "clean,,{},{},{}", self.clean_accuracy(), self.clean.right, self.clean.total)?;
+        for (noise, scores) in self.noises.iter().zip(self.scores.iter()) {
+            for (snr_db, score) in self.snrs_db.iter().zip(scores.iter()) {
+                writeln!(writer, "{},{},{},{},{}", noise, snr_db, score.accuracy(), score.right, score.total)?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Random recordings of other classes, each one at unit power, summed and repeated to the length
+    fn babble(&mut self, length: usize, class_name: &str, talkers: usize) -> io::Result<Vec<f64>> {
+        let candidates: Vec<&Vec<f64>> = self.babble_recordings
+                                             .iter()
+                                             .filter(|&(_, babble_class_name)| babble_class_name != class_name)
+                                             .map(|(talker, _)| talker)
+                                             .collect();
+        if candidates.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "NoiseBenchmark Error: No audible files of other classes to make babble from!"));
+        }
+
+        let mut babble = vec![0f64; length];
+        for _ in 0..talkers {
+            let talker = candidates[self.random_generator.gen_range(0usize, candidates.len())];
+            let offset = self.random_generator.gen_range(0usize, talker.len());
+            for (index, sample) in babble.iter_mut().enumerate() {
+                *sample += talker[(offset + index) % talker.len()];
+            }
+        }
+        Ok(babble)
+    }
+}
+
+fn read_samples<P: AsRef<Path>>(wav_file: P) -> io::Result<(Vec<f64>, usize)> {
+    let wav_handler = WavHandler::new(wav_file).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    let sample_rate = wav_handler.get_sample_rate() as usize;
+    Ok((wav_handler.extract_audio_data(), sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hound;
+
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn noisier_recordings_are_scored_separately() {
+        let babble_files = vec![
+            (PathBuf::from("dataset/a/1.wav"), String::from("a")),
+            (PathBuf::from("dataset/ah!/1.wav"), String::from("ah!")),
+        ];
+        let mut benchmark = NoiseBenchmark::new(
+            vec![BenchmarkNoise::White, BenchmarkNoise::Babble { talkers: 2usize }],
+            vec![30f64, -30f64],
+            babble_files,
+            1usize,
+        ).unwrap();
+
+        // A classifier only right when the samples are close to the clean ones
+        let (clean, _) = read_samples("dataset/a/2.wav").unwrap();
+        let clean_power = augmentation::power(&clean);
+        benchmark.evaluate("dataset/a/2.wav", "a", |samples, _| {
+            let difference: Vec<f64> = samples.iter().zip(clean.iter()).map(|(sample, clean)| sample - clean).collect();
+            if augmentation::power(&difference) < clean_power / 10f64 { String::from("a") } else { String::from("ah!") }
+        }).unwrap();
+
+        assert_eq!(benchmark.clean_accuracy(), 1f64);
+        assert_eq!(benchmark.accuracy(0usize, 0usize), 1f64);
+        assert_eq!(benchmark.accuracy(0usize, 1usize), 0f64);
+        assert_eq!(benchmark.accuracy(1usize, 0usize), 1f64);
+        assert_eq!(benchmark.accuracy(1usize, 1usize), 0f64);
+
+        let mut csv = Vec::new();
+        benchmark.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 6usize);
+        assert!(csv.contains("babble2,-30,0,0,1"));
+        assert_eq!(benchmark.table().lines().count(), 4usize);
+    }
+
+    #[test]
+    fn empty_babble_files_are_left_out() {
+        let empty_file = env::temp_dir().join(format!("dictawav-empty-babble-{}.wav", process::id()));
+        let wav_spec = hound::WavSpec {
+            channels: 1u16,
+            sample_rate: 16000u32,
+            bits_per_sample: 16u16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        hound::WavWriter::create(&empty_file, wav_spec).unwrap().finalize().unwrap();
+
+        let benchmark = NoiseBenchmark::new(
+            vec![BenchmarkNoise::Babble { talkers: 1usize }],
+            vec![10f64],
+            vec![(empty_file.clone(), String::from("ah!"))],
+            1usize,
+        );
+        fs::remove_file(&empty_file).unwrap();
+
+        let mut benchmark = benchmark.unwrap();
+        assert!(benchmark.babble_recordings.is_empty());
+        let evaluated = benchmark.evaluate("dataset/a/2.wav", "a", |_, _| String::from("a"));
+        assert_eq!(evaluated.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
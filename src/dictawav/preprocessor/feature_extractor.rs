@@ -0,0 +1,306 @@
+use std::f64;
+use super::filterbank::{MelFilterbank, MelScale};
+use super::log_mel::LogMelFilterbank;
+use super::log_spectrogram::LogSpectrogram;
+use super::mfcc::MFCC;
+use super::plp::PLP;
+
+/// Turns the magnitude spectrum of a frame into a feature vector
+pub trait FeatureExtractor {
+    fn compute(&mut self, spectrum: &[f64]) -> Vec<f64>;
+
+    /// Length of every vector returned by `compute`
+    fn output_dimension(&self) -> usize;
+}
+
+/// Layout of the mel filterbank
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterbankConfig {
+    pub filterbank_count: usize,
+    pub lowest_frequency: f64,
+    /// Defaults to half the sample rate of each file when `None`
+    pub highest_frequency: Option<f64>,
+    pub mel_scale: MelScale,
+}
+
+impl Default for FilterbankConfig {
+    fn default() -> FilterbankConfig {
+        FilterbankConfig {
+            filterbank_count: 26usize,
+            lowest_frequency: 0f64,
+            highest_frequency: None,
+            mel_scale: MelScale::Htk,
+        }
+    }
+}
+
+impl FilterbankConfig {
+    pub fn build(&self, sample_rate: usize, frame_size: usize) -> MelFilterbank {
+        let nyquist_frequency = sample_rate as f64 / 2f64;
+        let highest_frequency = self.highest_frequency.unwrap_or(nyquist_frequency);
+
+        self.check();
+        if self.lowest_frequency < 0f64 || self.lowest_frequency >= highest_frequency {
+            panic!("PreProcessor Error: Invalid filterbank frequency range!");
+        }
+        if highest_frequency > nyquist_frequency {
+            panic!("PreProcessor Error: Filterbank highest frequency is above half the sample rate!");
+        }
+
+        MelFilterbank::new(self.filterbank_count,
+                           sample_rate,
+                           frame_size,
+                           self.lowest_frequency,
+                           highest_frequency,
+                           self.mel_scale,
+        )
+    }
+
+    fn check(&self) {
+        if self.filterbank_count == 0usize {
+            panic!("PreProcessor Error: A filterbank needs at least one filter!");
+        }
+    }
+}
+
+/// Which cepstral coefficients are kept and how they are weighted
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CepstralConfig {
+    /// Number of coefficients computed, including c0
+    pub cepstra_count: usize,
+    /// Drops c0, which mostly carries the frame energy
+    pub drop_c0: bool,
+    /// Sinusoidal lifter parameter, as HTK's CEPLIFTER
+    pub lifter: Option<f64>,
+}
+
+impl Default for CepstralConfig {
+    fn default() -> CepstralConfig {
+        CepstralConfig {
+            cepstra_count: 13usize,
+            drop_c0: false,
+            lifter: None,
+        }
+    }
+}
+
+impl CepstralConfig {
+    pub fn output_dimension(&self) -> usize {
+        if self.drop_c0 { self.cepstra_count.saturating_sub(1usize) } else { self.cepstra_count }
+    }
+
+    /// Applies the lifter and drops c0 if asked to
+    pub fn finish(&self, cepstrum: Vec<f64>) -> Vec<f64> {
+        let skipped = if self.drop_c0 { 1usize } else { 0usize };
+
+        cepstrum.into_iter()
+                .enumerate()
+                .skip(skipped)
+                .map(
+                    |(index, coefficient)|
+                        match self.lifter {
+                            Some(lifter) => {
+                                coefficient * (1f64 + lifter / 2f64 * (f64::consts::PI * index as f64 / lifter).sin())
+                            }
+                            None => coefficient,
+                        }
+                ).collect()
+    }
+
+    fn check(&self) {
+        if self.output_dimension() == 0usize {
+            panic!("PreProcessor Error: At least one cepstral coefficient must be kept!");
+        }
+    }
+}
+
+/// The spectral front-ends available to the preprocessor
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FrontEnd {
+    /// Mel frequency cepstral coefficients
+    Mfcc { filterbank: FilterbankConfig, cepstra: CepstralConfig },
+    /// Log energies of the mel filterbank
+    LogMelFilterbank { filterbank: FilterbankConfig },
+    /// Log magnitudes averaged over `band_count` equally wide frequency bands
+    LogSpectrogram { band_count: usize },
+    /// Perceptual linear prediction cepstral coefficients from an all-pole model of `order` poles
+    Plp { filterbank: FilterbankConfig, order: usize, cepstra: CepstralConfig },
+}
+
+impl FrontEnd {
+    /// The 13 MFCCs from 26 mel filters
+    pub fn mfcc() -> FrontEnd {
+        FrontEnd::Mfcc {
+            filterbank: FilterbankConfig::default(),
+            cepstra: CepstralConfig::default(),
+        }
+    }
+
+    pub fn log_mel_filterbank() -> FrontEnd {
+        FrontEnd::LogMelFilterbank {
+            filterbank: FilterbankConfig::default(),
+        }
+    }
+
+    pub fn log_spectrogram() -> FrontEnd {
+        FrontEnd::LogSpectrogram {
+            band_count: 32usize,
+        }
+    }
+
+    /// 13 PLP cepstral coefficients from a 12th order model
+    pub fn plp() -> FrontEnd {
+        FrontEnd::Plp {
+            filterbank: FilterbankConfig::default(),
+            order: 12usize,
+            cepstra: CepstralConfig::default(),
+        }
+    }
+
+    /// Dimension of the features, known before any audio is read
+    pub fn output_dimension(&self) -> usize {
+        self.check();
+
+        match *self {
+            FrontEnd::Mfcc { ref cepstra, .. } => cepstra.output_dimension(),
+            FrontEnd::LogMelFilterbank { ref filterbank } => filterbank.filterbank_count,
+            FrontEnd::LogSpectrogram { band_count } => band_count,
+            FrontEnd::Plp { ref cepstra, .. } => cepstra.output_dimension(),
+        }
+    }
+
+    pub fn build(&self, sample_rate: usize, frame_size: usize) -> Box<dyn FeatureExtractor> {
+        self.check();
+
+        let feature_extractor: Box<dyn FeatureExtractor> = match *self {
+            FrontEnd::Mfcc { ref filterbank, cepstra } => {
+                Box::new(MFCC::new(filterbank.build(sample_rate, frame_size), cepstra))
+            }
+            FrontEnd::LogMelFilterbank { ref filterbank } => {
+                Box::new(LogMelFilterbank::new(filterbank.build(sample_rate, frame_size)))
+            }
+            FrontEnd::LogSpectrogram { band_count } => Box::new(LogSpectrogram::new(band_count, frame_size)),
+            FrontEnd::Plp { ref filterbank, order, cepstra } => {
+                Box::new(PLP::new(filterbank.build(sample_rate, frame_size), order, cepstra))
+            }
+        };
+
+        if feature_extractor.output_dimension() != self.output_dimension() {
+            panic!("PreProcessor Error: Feature extractor output dimension differs from the front-end one!");
+        }
+
+        feature_extractor
+    }
+
+    /// Panics on settings that can't give any feature, whatever the sample rate
+    fn check(&self) {
+        match *self {
+            FrontEnd::Mfcc { ref filterbank, ref cepstra } => {
+                filterbank.check();
+                cepstra.check();
+                if cepstra.cepstra_count > filterbank.filterbank_count {
+                    panic!("PreProcessor Error: MFCC can't have more cepstra than filters!");
+                }
+            }
+            FrontEnd::LogMelFilterbank { ref filterbank } => filterbank.check(),
+            FrontEnd::LogSpectrogram { band_count } => {
+                if band_count == 0usize {
+                    panic!("PreProcessor Error: A log spectrogram needs at least one band!");
+                }
+            }
+            FrontEnd::Plp { ref filterbank, order, ref cepstra } => {
+                filterbank.check();
+                cepstra.check();
+                if order == 0usize {
+                    panic!("PreProcessor Error: PLP needs a model with at least one pole!");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_ends_declare_their_output_dimension() {
+        let frame_size = 1024usize;
+        let spectrum: Vec<f64> = (0..frame_size).map(|bin| 1f64 / (1f64 + bin as f64)).collect();
+        let silence = vec![0f64; frame_size];
+
+        let configured_mfcc = FrontEnd::Mfcc {
+            filterbank: FilterbankConfig {
+                filterbank_count: 40usize,
+                lowest_frequency: 100f64,
+                highest_frequency: Some(8000f64),
+                mel_scale: MelScale::Slaney,
+            },
+            cepstra: CepstralConfig {
+                cepstra_count: 20usize,
+                drop_c0: true,
+                lifter: Some(22f64),
+            },
+        };
+        let front_ends = [FrontEnd::mfcc(), configured_mfcc, FrontEnd::log_mel_filterbank(), FrontEnd::log_spectrogram(), FrontEnd::plp()];
+
+        assert_eq!(configured_mfcc.output_dimension(), 19usize);
+        for front_end in &front_ends {
+            let mut feature_extractor = front_end.build(44100usize, frame_size);
+
+            for frame in &[&spectrum, &silence] {
+                let features = feature_extractor.compute(frame);
+                assert_eq!(features.len(), front_end.output_dimension());
+                assert!(features.iter().all(|value| value.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn lifter_weights_and_drops_c0() {
+        let cepstra = CepstralConfig {
+            cepstra_count: 3usize,
+            drop_c0: true,
+            lifter: Some(2f64),
+        };
+
+        let liftered = cepstra.finish(vec![5f64, 1f64, 1f64]);
+        assert_eq!(liftered.len(), 2usize);
+        assert!((liftered[0] - 2f64).abs() < 1e-12);
+        assert!((liftered[1] - 1f64).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one cepstral coefficient must be kept")]
+    fn empty_cepstra_are_rejected_before_their_dimension_is_used() {
+        let front_end = FrontEnd::Mfcc {
+            filterbank: FilterbankConfig::default(),
+            cepstra: CepstralConfig {
+                cepstra_count: 0usize,
+                drop_c0: true,
+                lifter: None,
+            },
+        };
+
+        front_end.output_dimension();
+    }
+
+    #[test]
+    #[should_panic(expected = "A filterbank needs at least one filter")]
+    fn empty_filterbanks_are_rejected_before_their_dimension_is_used() {
+        let front_end = FrontEnd::LogMelFilterbank {
+            filterbank: FilterbankConfig {
+                filterbank_count: 0usize,
+                ..FilterbankConfig::default()
+            },
+        };
+
+        front_end.output_dimension();
+    }
+
+    #[test]
+    #[should_panic(expected = "A log spectrogram needs at least one band")]
+    fn empty_spectrograms_are_rejected_before_their_dimension_is_used() {
+        FrontEnd::LogSpectrogram { band_count: 0usize }.output_dimension();
+    }
+}
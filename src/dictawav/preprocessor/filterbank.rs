@@ -0,0 +1,146 @@
+use std::f64;
+
+/// Smallest energy taken into a logarithm, avoiding infinities on silent frames
+const ENERGY_FLOOR: f64 = 1e-10;
+
+/// Formula used to convert between hertz and mels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MelScale {
+    /// 1127 ln(1 + f / 700), as used by HTK
+    Htk,
+    /// Linear below 1kHz and logarithmic above, as in Slaney's Auditory Toolbox
+    Slaney,
+}
+
+impl MelScale {
+    #[inline]
+    pub fn hertz_to_mel(self, hertz: f64) -> f64 {
+        match self {
+            MelScale::Htk => 1127f64 * (1f64 + hertz / 700f64).ln(),
+            MelScale::Slaney => {
+                if hertz < SLANEY_BREAK_FREQUENCY {
+                    hertz / SLANEY_LINEAR_STEP
+                } else {
+                    SLANEY_BREAK_FREQUENCY / SLANEY_LINEAR_STEP + (hertz / SLANEY_BREAK_FREQUENCY).ln() / SLANEY_LOG_STEP.ln() * 27f64
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn mel_to_hertz(self, mels: f64) -> f64 {
+        match self {
+            MelScale::Htk => 700f64 * ((mels / 1127f64).exp() - 1f64),
+            MelScale::Slaney => {
+                let break_mel = SLANEY_BREAK_FREQUENCY / SLANEY_LINEAR_STEP;
+                if mels < break_mel {
+                    mels * SLANEY_LINEAR_STEP
+                } else {
+                    SLANEY_BREAK_FREQUENCY * ((mels - break_mel) * SLANEY_LOG_STEP.ln() / 27f64).exp()
+                }
+            }
+        }
+    }
+}
+
+const SLANEY_BREAK_FREQUENCY: f64 = 1000f64;
+const SLANEY_LINEAR_STEP: f64 = 200f64 / 3f64;
+const SLANEY_LOG_STEP: f64 = 6.4f64;
+
+/// Triangular filters equally spaced on the mel scale
+pub struct MelFilterbank {
+    filterbank: Vec<[usize; 3]>,
+    center_frequencies: Vec<f64>,
+}
+
+impl MelFilterbank {
+    pub fn new(filterbank_count: usize,
+               sample_rate: usize,
+               frame_size: usize,
+               lowest_frequency: f64,
+               highest_frequency: f64,
+               mel_scale: MelScale,
+    ) -> MelFilterbank {
+        let lowest_mel = mel_scale.hertz_to_mel(lowest_frequency);
+        let highest_mel = mel_scale.hertz_to_mel(highest_frequency);
+        let delta_mel = (highest_mel - lowest_mel) / (filterbank_count as f64 + 1f64);
+
+        let mut bins = Vec::with_capacity(filterbank_count + 2);
+        let mut frequencies = Vec::with_capacity(filterbank_count + 2);
+        for pos in 0..(filterbank_count + 2) {
+            let frequency = mel_scale.mel_to_hertz(lowest_mel + pos as f64 * delta_mel);
+            bins.push(
+                (
+                    (frame_size as f64 + 1f64) * frequency / sample_rate as f64
+                ).floor() as usize
+            );
+            frequencies.push(frequency);
+        }
+
+        let mut filterbank = Vec::with_capacity(filterbank_count);
+        let mut center_frequencies = Vec::with_capacity(filterbank_count);
+        for pos in 0..filterbank_count {
+            filterbank.push([bins[pos], bins[pos + 1], bins[pos + 2]]);
+            center_frequencies.push(frequencies[pos + 1]);
+        }
+
+        MelFilterbank {
+            filterbank,
+            center_frequencies,
+        }
+    }
+
+    pub fn filterbank_count(&self) -> usize {
+        self.filterbank.len()
+    }
+
+    /// Center frequency of each filter in hertz
+    pub fn center_frequencies(&self) -> &[f64] {
+        &self.center_frequencies
+    }
+
+    /// Sums the spectrum weighted by each triangular filter
+    pub fn apply(&self, spectrum: &[f64]) -> Vec<f64> {
+        let mut filtered_values = vec![0f64; self.filterbank.len()];
+
+        for (current_filter, fb) in self.filterbank.iter().enumerate() {
+            let begin = fb[0];
+            let mid = fb[1];
+            let end = fb[2];
+
+            for (pos, value) in spectrum.iter().enumerate().take(mid).skip(begin) {
+                filtered_values[current_filter] += value * (pos - begin) as f64 / (mid - begin) as f64;
+            }
+
+            for (pos, value) in spectrum.iter().enumerate().take(end).skip(mid) {
+                filtered_values[current_filter] += value * (end - pos) as f64 / (end - mid) as f64;
+            }
+        }
+
+        filtered_values
+    }
+}
+
+/// Natural logarithm of each energy, floored to keep silent bands finite
+pub fn log_energies(energies: Vec<f64>) -> Vec<f64> {
+    energies.into_iter()
+            .map(|energy| energy.max(ENERGY_FLOOR).ln())
+            .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mel_scales_round_trip() {
+        for mel_scale in &[MelScale::Htk, MelScale::Slaney] {
+            for hertz in &[0f64, 300f64, 1000f64, 4000f64, 22050f64] {
+                let round_trip = mel_scale.mel_to_hertz(mel_scale.hertz_to_mel(*hertz));
+                assert!((round_trip - *hertz).abs() < 1e-6);
+            }
+        }
+        assert!((MelScale::Slaney.hertz_to_mel(1000f64) - 15f64).abs() < 1e-12);
+        assert!((MelScale::Slaney.hertz_to_mel(6400f64) - 42f64).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,23 @@
+use super::feature_extractor::FeatureExtractor;
+use super::filterbank::{self, MelFilterbank};
+
+/// Log energies of a mel filterbank, the MFCC pipeline without the DCT
+pub struct LogMelFilterbank {
+    filterbank: MelFilterbank,
+}
+
+impl LogMelFilterbank {
+    pub fn new(filterbank: MelFilterbank) -> LogMelFilterbank {
+        LogMelFilterbank { filterbank }
+    }
+}
+
+impl FeatureExtractor for LogMelFilterbank {
+    fn compute(&mut self, frame: &[f64]) -> Vec<f64> {
+        filterbank::log_energies(self.filterbank.apply(frame))
+    }
+
+    fn output_dimension(&self) -> usize {
+        self.filterbank.filterbank_count()
+    }
+}
@@ -0,0 +1,46 @@
+use super::feature_extractor::FeatureExtractor;
+use super::filterbank;
+
+/// Linear frequency log spectrogram
+///
+/// The non-redundant half of the spectrum is averaged over equally wide bands, so the output
+/// dimension doesn't depend on the sample rate of the file
+pub struct LogSpectrogram {
+    band_count: usize,
+    band_limits: Vec<(usize, usize)>,
+}
+
+impl LogSpectrogram {
+    pub fn new(band_count: usize, frame_size: usize) -> LogSpectrogram {
+        let bins_count = frame_size / 2usize + 1usize;
+        if band_count == 0usize || band_count > bins_count {
+            panic!("PreProcessor Error: Invalid number of spectrogram bands!");
+        }
+
+        let band_limits = (0..band_count).map(
+            |band|
+                (band * bins_count / band_count, (band + 1usize) * bins_count / band_count)
+        ).collect();
+
+        LogSpectrogram {
+            band_count,
+            band_limits,
+        }
+    }
+}
+
+impl FeatureExtractor for LogSpectrogram {
+    fn compute(&mut self, frame: &[f64]) -> Vec<f64> {
+        filterbank::log_energies(
+            self.band_limits.iter()
+                .map(
+                    |&(begin, end)|
+                        frame[begin..end].iter().sum::<f64>() / (end - begin) as f64
+                ).collect()
+        )
+    }
+
+    fn output_dimension(&self) -> usize {
+        self.band_count
+    }
+}
@@ -6,14 +6,18 @@ use self::rustdct::rustfft::FFTplanner;
 pub struct DCTHandler {
     dct: DCT2ViaFFT<f64>,
     size: usize,
+    kept_coefficients: usize,
 }
 
 impl DCTHandler {
-    pub fn new(size: usize) -> DCTHandler {
+    pub fn new(size: usize, kept_coefficients: usize) -> DCTHandler {
+        if kept_coefficients > size {
+            panic!("DCTHandler Error: Trying to keep more coefficients than the DCT size!");
+        }
         let mut planner = FFTplanner::new(false);
         let dct = DCT2ViaFFT::new(planner.plan_fft(size));
 
-        DCTHandler { dct, size }
+        DCTHandler { dct, size, kept_coefficients }
     }
 
     pub fn process(&mut self, mut input: Vec<f64>) -> Vec<f64> {
@@ -21,6 +25,6 @@ impl DCTHandler {
 
         self.dct.process(&mut input, &mut output);
 
-        output.into_iter().take(self.size / 2usize).collect()
+        output.into_iter().take(self.kept_coefficients).collect()
     }
 }
\ No newline at end of file
@@ -1,9 +1,15 @@
 mod fft_handler;
+mod feature_extractor;
+mod filterbank;
+mod log_mel;
+mod log_spectrogram;
 mod mfcc;
+mod plp;
 
 use std::f64;
 use self::fft_handler::FFTHandler;
-use self::mfcc::MFCC;
+pub use self::feature_extractor::{CepstralConfig, FeatureExtractor, FilterbankConfig, FrontEnd};
+pub use self::filterbank::MelScale;
 
 type Frame = Vec<f64>;
 
@@ -11,30 +17,22 @@ pub struct PreProcessor {
     samples_per_frame: usize,
     processed_frames: Vec<Frame>,
     fft_handler: FFTHandler,
-    mfcc: mfcc::MFCC,
+    feature_extractor: Box<dyn FeatureExtractor>,
 }
 
 impl PreProcessor {
-    pub fn new(sample_rate: usize) -> PreProcessor {
+    pub fn new(sample_rate: usize, front_end: &FrontEnd) -> PreProcessor {
         // Divide by 50 to get approximately 20ms sized frames
         let samples_per_frame = PreProcessor::get_next_power_of_2(sample_rate / 50usize);
         let processed_frames: Vec<Frame> = Vec::new();
         let fft_handler = FFTHandler::new(samples_per_frame);
-        let filterbank_count = 26usize;
-        let lowest_frequency = 0f64;
-        let highest_frequency = PreProcessor::get_highest_frequency(sample_rate);
-        let mfcc = MFCC::new(filterbank_count,
-                             sample_rate,
-                             samples_per_frame,
-                             lowest_frequency,
-                             highest_frequency,
-        );
+        let feature_extractor = front_end.build(sample_rate, samples_per_frame);
 
         PreProcessor {
             samples_per_frame,
             processed_frames,
             fft_handler,
-            mfcc,
+            feature_extractor,
         }
     }
 
@@ -103,7 +101,7 @@ impl PreProcessor {
     #[inline]
     fn process_and_add_frame(&mut self, frame: Frame) {
         self.processed_frames.push(
-            self.mfcc.compute(
+            self.feature_extractor.compute(
                 &self.fft_handler.process(frame)
             )
         );
@@ -138,11 +136,6 @@ impl PreProcessor {
         }
         base2
     }
-
-    #[inline]
-    fn get_highest_frequency(sample_rate: usize) -> f64 {
-        sample_rate as f64 / 2f64
-    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,129 @@
+use std::f64;
+use super::feature_extractor::{CepstralConfig, FeatureExtractor};
+use super::filterbank::MelFilterbank;
+
+/// Perceptual linear prediction cepstral coefficients
+///
+/// Follows the HTK flavour of PLP: the power spectrum goes through a mel filterbank, an equal
+/// loudness curve and a cubic root compression, then an all-pole model is fitted to it and
+/// converted to cepstral coefficients
+#[allow(clippy::upper_case_acronyms)]
+pub struct PLP {
+    filterbank: MelFilterbank,
+    order: usize,
+    cepstra: CepstralConfig,
+    equal_loudness: Vec<f64>,
+}
+
+impl PLP {
+    pub fn new(filterbank: MelFilterbank, order: usize, cepstra: CepstralConfig) -> PLP {
+        let equal_loudness = filterbank.center_frequencies()
+                                       .iter()
+                                       .map(|frequency| PLP::equal_loudness_weight(*frequency))
+                                       .collect();
+
+        PLP {
+            filterbank,
+            order,
+            cepstra,
+            equal_loudness,
+        }
+    }
+
+    /// Hermansky's approximation of the human sensitivity at 40dB
+    #[inline]
+    fn equal_loudness_weight(frequency: f64) -> f64 {
+        let squared = (2f64 * f64::consts::PI * frequency).powi(2);
+        (squared + 56.8e6) * squared.powi(2) / ((squared + 6.3e6).powi(2) * (squared + 0.38e9))
+    }
+
+    /// Autocorrelation of the compressed auditory spectrum through its inverse DFT
+    fn autocorrelation(&self, spectrum: &[f64]) -> Vec<f64> {
+        // Edge bands are duplicated to cover 0 and the Nyquist frequency
+        let mut extended = Vec::with_capacity(spectrum.len() + 2usize);
+        extended.push(spectrum[0]);
+        extended.extend(spectrum.iter());
+        extended.push(spectrum[spectrum.len() - 1usize]);
+
+        let last = extended.len() - 1usize;
+        (0..(self.order + 1usize)).map(
+            |lag| {
+                let mut sum = extended[0] + if lag % 2usize == 0 { extended[last] } else { -extended[last] };
+                for (index, value) in extended.iter().enumerate().take(last).skip(1) {
+                    sum += 2f64 * value * (f64::consts::PI * (lag * index) as f64 / last as f64).cos();
+                }
+                sum / (2f64 * last as f64)
+            }
+        ).collect()
+    }
+
+    /// Levinson-Durbin recursion, returns the predictor and the prediction error
+    fn linear_prediction(&self, autocorrelation: &[f64]) -> (Vec<f64>, f64) {
+        let mut coefficients = vec![0f64; self.order + 1usize];
+        coefficients[0] = 1f64;
+        let mut error = autocorrelation[0];
+
+        for step in 1..(self.order + 1usize) {
+            if error <= 0f64 {
+                break;
+            }
+
+            let mut accumulated = autocorrelation[step];
+            for index in 1..step {
+                accumulated += coefficients[index] * autocorrelation[step - index];
+            }
+            let reflection = -accumulated / error;
+
+            let previous = coefficients.clone();
+            for index in 1..step {
+                coefficients[index] = previous[index] + reflection * previous[step - index];
+            }
+            coefficients[step] = reflection;
+            error *= 1f64 - reflection * reflection;
+        }
+
+        (coefficients, error)
+    }
+
+    /// Cepstrum of the all-pole model, the first coefficient is the log of its gain
+    fn cepstrum(&self, coefficients: &[f64], error: f64) -> Vec<f64> {
+        let cepstra_count = self.cepstra.cepstra_count;
+        let mut cepstrum = vec![0f64; cepstra_count];
+        cepstrum[0] = error.max(1e-10).ln();
+
+        for index in 1..cepstra_count {
+            let mut value = if index <= self.order { -coefficients[index] } else { 0f64 };
+            for previous in index.saturating_sub(self.order).max(1usize)..index {
+                value -= (previous as f64 / index as f64) * cepstrum[previous] * coefficients[index - previous];
+            }
+            cepstrum[index] = value;
+        }
+
+        cepstrum
+    }
+}
+
+impl FeatureExtractor for PLP {
+    fn compute(&mut self, frame: &[f64]) -> Vec<f64> {
+        let power_spectrum: Vec<f64> = frame.iter().map(|magnitude| magnitude * magnitude).collect();
+
+        let auditory_spectrum: Vec<f64> = self.filterbank
+                                              .apply(&power_spectrum)
+                                              .into_iter()
+                                              .zip(self.equal_loudness.iter())
+                                              .map(|(energy, weight)| (energy * weight).cbrt())
+                                              .collect();
+
+        let autocorrelation = self.autocorrelation(&auditory_spectrum);
+        if autocorrelation[0] <= 0f64 {
+            return self.cepstra.finish(vec![0f64; self.cepstra.cepstra_count]);
+        }
+
+        let (coefficients, error) = self.linear_prediction(&autocorrelation);
+        self.cepstra.finish(self.cepstrum(&coefficients, error))
+    }
+
+    fn output_dimension(&self) -> usize {
+        self.cepstra.output_dimension()
+    }
+}
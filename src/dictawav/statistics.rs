@@ -0,0 +1,16 @@
+use super::wisard::WisardStatistics;
+
+/// Size and saturation of a trained model
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelStatistics {
+    pub wisard: WisardStatistics,
+    pub kernels_count: usize,
+    /// Kernels painted by at least one training frame since kernels were last set
+    pub activated_kernels_count: usize,
+}
+
+impl ModelStatistics {
+    pub fn activated_kernels_fraction(&self) -> f64 {
+        self.activated_kernels_count as f64 / self.kernels_count as f64
+    }
+}
@@ -1,7 +1,6 @@
 extern crate hound;
 
 use std::path;
-use std::{i8, i16, i32};
 
 /// A handler to wav files using Hound library
 ///
@@ -87,7 +86,7 @@ mod tests {
     #[test]
     fn read_against_hound_test() {
         let mut wav_reader = WavReader::open("testsamples/waveformatex-ieeefloat-44100Hz-mono.wav").unwrap();
-        let hound_samples: Vec<f32> = wav_reader.samples::<f32>().map(|sample| sample.unwrap()).collect();
+        let hound_samples: Vec<f64> = wav_reader.samples::<f32>().map(|sample| f64::from(sample.unwrap())).collect();
 
         let wav_handler = WavHandler::new("testsamples/waveformatex-ieeefloat-44100Hz-mono.wav").unwrap();
 
@@ -97,43 +96,43 @@ mod tests {
     #[test]
     fn read_i8bit_wav_file_test() { // Test based on Hound's tests
         let wav_handler = WavHandler::new("testsamples/pcmwaveformat-8bit-44100Hz-mono.wav").unwrap();
-        let file_data = vec![19i8, -53i8, 89i8, -127i8];
-        let file_data_f32_conversion: Vec<f32> = file_data.iter()
-                                                          .map(|x| (*x as f32) * (1f32 / (i8::MAX as f32 + 1f32)))
+        let file_data = [19i8, -53i8, 89i8, -127i8];
+        let file_data_f64_conversion: Vec<f64> = file_data.iter()
+                                                          .map(|x| (*x as f64) * (1f64 / (i8::MAX as f64 + 1f64)))
                                                           .collect();
 
-        assert_eq!(file_data_f32_conversion, wav_handler.audio_data);
+        assert_eq!(file_data_f64_conversion, wav_handler.audio_data);
     }
 
     #[test]
     fn read_i16bit_wav_file_test() { // Test based on Hound's tests
         let wav_handler = WavHandler::new("testsamples/pcmwaveformat-16bit-44100Hz-mono.wav").unwrap();
-        let file_data = vec![2i16, -3i16, 5i16, -7i16];
-        let file_data_f32_conversion: Vec<f32> = file_data.iter()
-                                                          .map(|x| (*x as f32) * (1f32 / (i16::MAX as f32 + 1f32)))
+        let file_data = [2i16, -3i16, 5i16, -7i16];
+        let file_data_f64_conversion: Vec<f64> = file_data.iter()
+                                                          .map(|x| (*x as f64) * (1f64 / (i16::MAX as f64 + 1f64)))
                                                           .collect();
 
-        assert_eq!(file_data_f32_conversion, wav_handler.audio_data);
+        assert_eq!(file_data_f64_conversion, wav_handler.audio_data);
     }
 
     #[test]
     fn read_i32bit_wav_file_test() { // Test based on Hound's tests
         let wav_handler = WavHandler::new("testsamples/waveformatextensible-32bit-48kHz-stereo.wav").unwrap();
-        let file_data = vec![19, -229373, 33587161, -2147483497];
-        let mut file_data_f32_conversion: Vec<f32> = file_data.iter()
-                                                              .map(|x| (*x as f32) * (1f32 / (i32::MAX as f32 + 1f32)))
+        let file_data = [19, -229373, 33587161, -2147483497];
+        let mut file_data_f64_conversion: Vec<f64> = file_data.iter()
+                                                              .map(|x| (*x as f64) * (1f64 / (i32::MAX as f64 + 1f64)))
                                                               .collect();
 
-        file_data_f32_conversion = WavHandler::convert_to_mono(wav_handler.wav_spec.channels, file_data_f32_conversion);
+        file_data_f64_conversion = WavHandler::convert_to_mono(wav_handler.wav_spec.channels, &file_data_f64_conversion);
 
-        assert_eq!(file_data_f32_conversion, wav_handler.audio_data);
+        assert_eq!(file_data_f64_conversion, wav_handler.audio_data);
     }
 
     #[test]
     fn convert_to_mono_test() {
-        let data = vec![1f32, 3f32, 4f32, 8f32, 5f32, 11f32];
+        let data = vec![1f64, 3f64, 4f64, 8f64, 5f64, 11f64];
         let channels = 2u16;
-        assert_eq!(WavHandler::convert_to_mono(channels, data), &[2f32, 6f32, 8f32]);
+        assert_eq!(WavHandler::convert_to_mono(channels, &data), &[2f64, 6f64, 8f64]);
     }
 }
 
@@ -0,0 +1,108 @@
+use std::collections::hash_map::HashMap;
+
+/// Largest probability still counted as no ram recognising the retina
+const NO_VOTES_PROBABILITY: f64 = 0.0001;
+
+/// How the threshold a ram value must exceed to vote is chosen when bleaching
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BleachingPolicy {
+    /// Lowest threshold from the bleaching threshold whose confidence reaches the minimum
+    /// confidence, keeping the unbleached votes if no ram is left voting
+    ///
+    /// Confidence is taken to grow with the threshold, so the thresholds are bisected. Where it
+    /// dips instead, the threshold found reaches the minimum confidence but may not be the lowest
+    /// one that does.
+    MinimumConfidence,
+    /// Always uses the same threshold
+    Fixed { threshold: u64 },
+    /// Raises the threshold from the bleaching threshold until a single class has the most votes,
+    /// keeping the last votes with some ram voting, scanning the thresholds in increasing order
+    SingleWinner,
+    /// Uses the threshold giving the highest confidence, the lowest one among equals
+    MaximalConfidence,
+}
+
+/// Ram values of every class sorted once, so the votes at any threshold are counted with a binary
+/// search instead of a pass over every ram
+///
+/// Only the thresholds changing some vote are searched.
+pub struct SortedRamValues {
+    classes: Vec<(String, Vec<u64>)>,
+    distinct_values: Vec<u64>,
+    rams_count: f64,
+}
+
+impl SortedRamValues {
+    pub fn new(rams_results: &HashMap<String, Vec<u64>>, rams_count: f64) -> SortedRamValues {
+        let classes: Vec<(String, Vec<u64>)> = rams_results.iter().map(
+            |(class_name, ram_values)| {
+                let mut sorted_values = ram_values.clone();
+                sorted_values.sort_unstable();
+                (class_name.clone(), sorted_values)
+            }
+        ).collect();
+
+        let mut distinct_values: Vec<u64> = classes.iter().flat_map(|(_, values)| values.iter().cloned()).collect();
+        distinct_values.sort_unstable();
+        distinct_values.dedup();
+
+        SortedRamValues {
+            classes,
+            distinct_values,
+            rams_count,
+        }
+    }
+
+    /// Fraction of the rams of each class whose value is above the threshold
+    pub fn probabilities(&self, threshold: u64) -> HashMap<String, f64> {
+        self.classes
+            .iter()
+            .map(
+                |(class_name, sorted_values)| {
+                    let votes = sorted_values.len() - sorted_values.partition_point(|value| *value <= threshold);
+                    (class_name.clone(), votes as f64 / self.rams_count)
+                }
+            ).collect()
+    }
+
+    /// Thresholds from `first` on where the votes of some class change, every threshold in between
+    /// giving the same votes as the previous one
+    pub fn thresholds_from<'a>(&'a self, first: u64) -> impl Iterator<Item=u64> + 'a {
+        let start = self.distinct_values.partition_point(|value| *value <= first);
+        Some(first).into_iter().chain(self.distinct_values[start..].iter().cloned())
+    }
+}
+
+/// Whether no class got a meaningful share of votes
+pub fn no_votes(probabilities: &HashMap<String, f64>) -> bool {
+    probabilities.values().all(|probability| *probability <= NO_VOTES_PROBABILITY)
+}
+
+/// Whether a single class has the highest probability
+pub fn has_single_winner(probabilities: &HashMap<String, f64>) -> bool {
+    let max = probabilities.values().cloned().fold(0f64, f64::max);
+    probabilities.values().filter(|&probability| *probability == max).count() == 1usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_skip_values_without_changes() {
+        let mut rams_results = HashMap::new();
+        rams_results.insert(String::from("a"), vec![0u64, 3u64, 3u64, 7u64]);
+        rams_results.insert(String::from("b"), vec![1u64, 2u64, 9u64, 0u64]);
+        let sorted_values = SortedRamValues::new(&rams_results, 4f64);
+
+        assert_eq!(sorted_values.thresholds_from(2u64).collect::<Vec<u64>>(), vec![2u64, 3u64, 7u64, 9u64]);
+
+        for threshold in 0..11u64 {
+            let probabilities = sorted_values.probabilities(threshold);
+            for (class_name, ram_values) in &rams_results {
+                let votes = ram_values.iter().filter(|&value| *value > threshold).count();
+                assert_eq!(probabilities[class_name], votes as f64 / 4f64);
+            }
+        }
+    }
+}
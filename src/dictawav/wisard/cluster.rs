@@ -0,0 +1,138 @@
+use std::rc::Rc;
+use super::discriminator::Discriminator;
+use super::ram::RamMerge;
+use super::statistics::ClassStatistics;
+
+/// ClusWiSARD settings, letting a class learn with several discriminators
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Clustering {
+    /// A sample whose best score among the discriminators of its class is below it starts a new one
+    pub score_threshold: f64,
+    /// Most discriminators a class can have, the best one learns once it is reached
+    pub max_discriminators: usize,
+}
+
+/// Discriminators of a single class, only one unless the class is clustered
+#[derive(Serialize, Deserialize)]
+pub struct ClassDiscriminators {
+    discriminators: Vec<Discriminator>,
+}
+
+impl ClassDiscriminators {
+    pub fn new(discriminator: Discriminator) -> ClassDiscriminators {
+        ClassDiscriminators {
+            discriminators: vec![discriminator],
+        }
+    }
+
+    pub fn set_ram_address_mapping(&mut self, ram_address_mapping: &Rc<Vec<usize>>) {
+        for discriminator in &mut self.discriminators {
+            discriminator.set_ram_address_mapping(ram_address_mapping.clone());
+        }
+    }
+
+    /// Trains the discriminator recognising the retina best, or a new one when clustering allows it
+    pub fn train<F: FnOnce() -> Discriminator>(&mut self, retina: &[bool], clustering: Option<Clustering>, new_discriminator: F) {
+        let (best_index, best_score) = self.best_discriminator(retina);
+
+        if let Some(clustering) = clustering {
+            if best_score < clustering.score_threshold && self.discriminators.len() < clustering.max_discriminators {
+                let mut discriminator = new_discriminator();
+                discriminator.train(retina);
+                self.discriminators.push(discriminator);
+                return;
+            }
+        }
+
+        self.discriminators[best_index].train(retina);
+    }
+
+    /// Forgets the retina from the best discriminator that was trained with it
+    pub fn forget(&mut self, retina: &[bool]) -> bool {
+        let mut scores: Vec<(usize, f64)> = self.discriminators
+                                                .iter()
+                                                .enumerate()
+                                                .map(|(index, discriminator)| (index, discriminator.score(retina)))
+                                                .collect();
+        scores.sort_by(|first, second| second.1.partial_cmp(&first.1).unwrap().then(first.0.cmp(&second.0)));
+
+        scores.into_iter().any(|(index, _)| self.discriminators[index].forget(retina))
+    }
+
+    /// Adds the discriminators of another class as clusters of their own while there are fewer
+    /// than `max_discriminators`, merging each of the rest into the most similar cluster
+    pub fn merge(&mut self, other: ClassDiscriminators, mode: RamMerge, max_discriminators: usize) {
+        for other_discriminator in other.discriminators {
+            if self.discriminators.len() < max_discriminators {
+                self.discriminators.push(other_discriminator);
+                continue;
+            }
+
+            let mut most_similar = (0usize, self.discriminators[0].similarity(&other_discriminator));
+            for (index, discriminator) in self.discriminators.iter().enumerate().skip(1) {
+                let similarity = discriminator.similarity(&other_discriminator);
+                if similarity > most_similar.1 {
+                    most_similar = (index, similarity);
+                }
+            }
+            self.discriminators[most_similar.0].merge(&other_discriminator, mode);
+        }
+    }
+
+    /// Ram values of the discriminator recognising the retina best
+    pub fn classify(&self, retina: &[bool]) -> Vec<u64> {
+        let (best_index, _) = self.best_discriminator(retina);
+        self.discriminators[best_index].classify(retina)
+    }
+
+    pub fn trained_examples(&self) -> u64 {
+        self.discriminators.iter().map(|discriminator| discriminator.trained_examples()).sum()
+    }
+
+    /// Statistics summed over the discriminators, the occupancy being their mean
+    pub fn statistics(&self, class_name: &str) -> ClassStatistics {
+        let mut statistics = self.discriminators[0].statistics(class_name);
+        for discriminator in &self.discriminators[1..] {
+            let other = discriminator.statistics(class_name);
+            statistics.trained_examples += other.trained_examples;
+            statistics.rams_count += other.rams_count;
+            statistics.occupied_addresses.extend(other.occupied_addresses);
+            statistics.occupancy += other.occupancy;
+            for (value, count) in other.counter_histogram {
+                *statistics.counter_histogram.entry(value).or_insert(0usize) += count;
+            }
+            statistics.saturated_counters += other.saturated_counters;
+            statistics.memory_bytes += other.memory_bytes;
+        }
+        statistics.occupancy /= self.discriminators.len() as f64;
+        statistics.discriminators_count = self.discriminators.len();
+
+        statistics
+    }
+
+    pub fn retina_hit_counts(&self) -> Vec<u64> {
+        let mut hit_counts = self.discriminators[0].retina_hit_counts();
+        for discriminator in &self.discriminators[1..] {
+            for (total, count) in hit_counts.iter_mut().zip(discriminator.retina_hit_counts()) {
+                *total += count;
+            }
+        }
+        hit_counts
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.discriminators.iter().map(|discriminator| discriminator.memory_bytes()).sum()
+    }
+
+    /// Index and score of the best discriminator, the oldest one among equals
+    fn best_discriminator(&self, retina: &[bool]) -> (usize, f64) {
+        let mut best = (0usize, self.discriminators[0].score(retina));
+        for (index, discriminator) in self.discriminators.iter().enumerate().skip(1) {
+            let score = discriminator.score(retina);
+            if score > best.1 {
+                best = (index, score);
+            }
+        }
+        best
+    }
+}
@@ -1,11 +1,34 @@
 use std::rc::Rc;
-use super::ram::Ram;
+use super::ram::{Ram, RamMerge};
+use super::ram_storage::CounterWidth;
+use super::statistics::ClassStatistics;
+use std::collections::{BTreeMap, HashSet};
 
+/// Retina positions read by each ram, in the order of the bits of its addresses
+pub fn ram_retina_positions(retina_size: usize, ram_num_bits: usize, ram_address_mapping: &[usize]) -> Vec<Vec<usize>> {
+    let full_rams_count = retina_size / ram_num_bits;
+    let rest_of_positions = retina_size % ram_num_bits;
+
+    let mut positions: Vec<Vec<usize>> = (0..full_rams_count).map(
+        |ram_index| ram_address_mapping[ram_index * ram_num_bits..(ram_index + 1usize) * ram_num_bits].to_vec()
+    ).collect();
+    if rest_of_positions != 0usize {
+        // Same positions train uses for the remaining smaller ram
+        let first_position = retina_size - rest_of_positions - 1usize;
+        positions.push(ram_address_mapping[first_position..first_position + rest_of_positions].to_vec());
+    }
+
+    positions
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Discriminator {
     retina_size: usize,
     ram_num_bits: usize,
     rams_count: usize,
     rams: Vec<Ram>,
+    trained_examples: u64,
+    #[serde(skip)]
     ram_address_mapping: Rc<Vec<usize>>,
 }
 
@@ -15,6 +38,7 @@ impl Discriminator {
         ram_num_bits: usize,
         ram_address_mapping: Rc<Vec<usize>>,
         is_cumulative: bool,
+        counter_width: CounterWidth,
     ) -> Discriminator {
         if ram_num_bits > 62usize {
             panic!("WiSARD ERROR: Representation overflow due to number of bits");
@@ -25,14 +49,14 @@ impl Discriminator {
 
         if rest == 0 {
             for _ in 0..rams_count {
-                rams.push(Ram::new(ram_num_bits, is_cumulative));
+                rams.push(Ram::new(ram_num_bits, is_cumulative, counter_width));
             }
         } else {
             for _ in 0..(rams_count - 1usize) {
-                rams.push(Ram::new(ram_num_bits, is_cumulative));
+                rams.push(Ram::new(ram_num_bits, is_cumulative, counter_width));
             }
             // The remaining smaller ram
-            rams.push(Ram::new(rest, is_cumulative));
+            rams.push(Ram::new(rest, is_cumulative, counter_width));
         }
 
         Discriminator {
@@ -40,14 +64,54 @@ impl Discriminator {
             ram_num_bits,
             rams_count,
             rams,
+            trained_examples: 0u64,
             ram_address_mapping,
         }
     }
 
+    /// Shares the address mapping of its Wisard again after a load
+    pub fn set_ram_address_mapping(&mut self, ram_address_mapping: Rc<Vec<usize>>) {
+        self.ram_address_mapping = ram_address_mapping;
+    }
+
+    pub fn statistics(&self, class_name: &str) -> ClassStatistics {
+        let mut occupied_addresses = Vec::with_capacity(self.rams_count);
+        let mut address_space = 0f64;
+        let mut counter_histogram = BTreeMap::new();
+        let mut saturated_counters = 0usize;
+
+        for ram in &self.rams {
+            let counter_max = ram.counter_max();
+            let mut occupied = 0usize;
+            for (_, value) in ram.entries().filter(|&(_, value)| value > 0u64) {
+                occupied += 1usize;
+                *counter_histogram.entry(value).or_insert(0usize) += 1usize;
+                if value == counter_max {
+                    saturated_counters += 1usize;
+                }
+            }
+            occupied_addresses.push(occupied);
+            address_space += ram.address_space() as f64;
+        }
+
+        ClassStatistics {
+            class_name: String::from(class_name),
+            trained_examples: self.trained_examples,
+            rams_count: self.rams_count,
+            occupancy: occupied_addresses.iter().sum::<usize>() as f64 / address_space,
+            occupied_addresses,
+            counter_histogram,
+            saturated_counters,
+            memory_bytes: self.memory_bytes(),
+            discriminators_count: 1usize,
+        }
+    }
+
     pub fn train(&mut self, retina: &[bool]) {
         let mut address: usize;
         let mut base: usize;
         let mut ram_index = 0usize;
+        self.trained_examples += 1u64;
 
         // Each group os ram_num_bits is related with a ram
         for index in (0..(self.retina_size - self.ram_num_bits + 1)).step_by(self.ram_num_bits) {
@@ -85,45 +149,103 @@ impl Discriminator {
         }
     }
 
-    pub fn forget(&mut self, retina: &[bool]) {
-        let mut address: usize;
-        let mut base: usize;
-        let mut ram_index = 0usize;
-
-        // Each group os ram_num_bits is related with a ram
-        for index in (0..(self.retina_size - self.ram_num_bits + 1)).step_by(self.ram_num_bits) {
-            address = 0usize;
-            base = 1usize;
+    /// Adds what another discriminator of the same retina learned, counting its examples as well
+    ///
+    /// Cumulative rams merged with `RamMerge::Or` keep the larger count, so only as many examples
+    /// as the larger discriminator learned can be forgotten and that is the count kept.
+    pub fn merge(&mut self, other: &Discriminator, mode: RamMerge) {
+        for (ram, other_ram) in self.rams.iter_mut().zip(other.rams.iter()) {
+            ram.merge(other_ram, mode);
+        }
+        self.trained_examples = match mode {
+            RamMerge::Or if self.rams[0].is_cumulative() => self.trained_examples.max(other.trained_examples),
+            _ => self.trained_examples + other.trained_examples,
+        };
+    }
 
-            for bit_index in 0..self.ram_num_bits {
-                if retina[self.ram_address_mapping[index + bit_index]] {
-                    address += base;
+    /// Fraction of the addresses stored by either discriminator that both stored, averaged over
+    /// the rams
+    pub fn similarity(&self, other: &Discriminator) -> f64 {
+        let mut similarity = 0f64;
+        for (ram, other_ram) in self.rams.iter().zip(other.rams.iter()) {
+            let stored: HashSet<usize> = ram.entries().filter(|&(_, value)| value > 0u64).map(|(address, _)| address).collect();
+            let mut shared = 0usize;
+            let mut either = stored.len();
+            for (address, _) in other_ram.entries().filter(|&(_, value)| value > 0u64) {
+                if stored.contains(&address) {
+                    shared += 1usize;
+                } else {
+                    either += 1usize;
                 }
-                base *= 2usize;
             }
+            if either > 0usize {
+                similarity += shared as f64 / either as f64;
+            }
+        }
+        similarity / self.rams_count as f64
+    }
 
-            ram_index = index / self.ram_num_bits;
-            self.rams[ram_index].remove(address);
+    /// Examples trained minus examples forgotten
+    pub fn trained_examples(&self) -> u64 {
+        self.trained_examples
+    }
+
+    /// Removes a retina from the rams, leaving them untouched when some ram never stored its
+    /// address, which means the retina wasn't trained
+    pub fn forget(&mut self, retina: &[bool]) -> bool {
+        let addresses = self.addresses(retina);
+        if self.rams.iter().zip(addresses.iter()).any(|(ram, address)| ram.get(*address) == 0u64) {
+            return false;
         }
 
-        // The remaining retina when retina length isn't a multiple of ram_num_bits
-        let rest_of_positions = self.retina_size % self.ram_num_bits;
-        if rest_of_positions != 0usize {
-            address = 0usize;
-            base = 1usize;
+        for (ram, address) in self.rams.iter_mut().zip(addresses) {
+            ram.remove(address);
+        }
+        self.trained_examples = self.trained_examples.saturating_sub(1u64);
+        true
+    }
 
-            for bit_index in 0..rest_of_positions {
-                if retina[
-                    self.ram_address_mapping[
-                        self.retina_size - rest_of_positions - 1usize + bit_index
-                        ]
-                    ] {
-                    address += base;
+    /// Address each ram reads from a retina, built as in train
+    fn addresses(&self, retina: &[bool]) -> Vec<usize> {
+        ram_retina_positions(self.retina_size, self.ram_num_bits, &self.ram_address_mapping)
+            .iter()
+            .map(
+                |positions|
+                    positions.iter()
+                             .enumerate()
+                             .filter(|&(_, position)| retina[*position])
+                             .map(|(bit_index, _)| 1usize << bit_index)
+                             .sum()
+            ).collect()
+    }
+
+    /// Fraction of the rams that stored the address the retina gives them
+    pub fn score(&self, retina: &[bool]) -> f64 {
+        let votes = self.classify(retina).into_iter().filter(|value| *value > 0u64).count();
+        votes as f64 / self.rams_count as f64
+    }
+
+    /// Heap memory taken by the rams
+    pub fn memory_bytes(&self) -> usize {
+        self.rams.iter().map(|ram| ram.memory_bytes()).sum()
+    }
+
+    /// How many times each retina position was set in what the rams stored, weighted by the ram values
+    pub fn retina_hit_counts(&self) -> Vec<u64> {
+        let mut hit_counts = vec![0u64; self.retina_size];
+        let positions = ram_retina_positions(self.retina_size, self.ram_num_bits, &self.ram_address_mapping);
+
+        for (ram, ram_positions) in self.rams.iter().zip(positions.iter()) {
+            for (address, value) in ram.entries() {
+                for (bit_index, position) in ram_positions.iter().enumerate() {
+                    if address & (1usize << bit_index) != 0usize {
+                        hit_counts[*position] += value;
+                    }
                 }
-                base *= 2usize;
             }
-            self.rams[ram_index + 1].remove(address);
         }
+
+        hit_counts
     }
 
     pub fn classify(&self, retina: &[bool]) -> Vec<u64> {
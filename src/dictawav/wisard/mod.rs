@@ -1,21 +1,156 @@
 extern crate rand;
 
+mod bleaching;
+mod cluster;
 mod ram;
+mod regression;
+mod ram_storage;
 mod discriminator;
+mod statistics;
 
+use std::error::Error;
+use std::fmt;
+use std::mem;
 use std::rc::Rc;
-use std::collections::hash_map::HashMap;
-use self::rand::Rng;
+use std::collections::hash_map::{Entry, HashMap};
+use self::rand::{Rng, SeedableRng, StdRng};
+use self::bleaching::SortedRamValues;
+pub use self::bleaching::BleachingPolicy;
+use self::cluster::ClassDiscriminators;
+pub use self::cluster::Clustering;
 use self::discriminator::Discriminator;
+pub use self::ram::RamMerge;
+pub use self::ram_storage::CounterWidth;
+pub use self::regression::{Aggregation, RegressionWisard};
+pub use self::statistics::{ClassStatistics, WisardStatistics};
 
+/// Why a retina couldn't be forgotten, the model is left untouched
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ForgetError {
+    UnknownClass(String),
+    /// The class has no examples left to forget
+    NoTrainedExamples(String),
+    /// Some ram of the class never stored the address of the retina
+    UntrainedSample(String),
+}
+
+impl fmt::Display for ForgetError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ForgetError::UnknownClass(ref class_name) => {
+                write!(formatter, "WiSARD Error: Trying to forget a sample of unknown class {}!", class_name)
+            }
+            ForgetError::NoTrainedExamples(ref class_name) => {
+                write!(formatter, "WiSARD Error: Class {} has no trained examples to forget!", class_name)
+            }
+            ForgetError::UntrainedSample(ref class_name) => {
+                write!(formatter, "WiSARD Error: Trying to forget a sample class {} was not trained with!", class_name)
+            }
+        }
+    }
+}
+
+impl Error for ForgetError {}
+
+/// Why a vocabulary change couldn't be made, the model is left untouched
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VocabularyError {
+    UnknownClass(String),
+    /// The new name is already taken by another class
+    ClassExists(String),
+    /// A class can't be merged into itself
+    SameClass(String),
+    /// A class can't be split without samples for the classes replacing it
+    NoSamples(String),
+}
+
+impl fmt::Display for VocabularyError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VocabularyError::UnknownClass(ref class_name) => {
+                write!(formatter, "WiSARD Error: Unknown class {}!", class_name)
+            }
+            VocabularyError::ClassExists(ref class_name) => {
+                write!(formatter, "WiSARD Error: Class {} already exists!", class_name)
+            }
+            VocabularyError::SameClass(ref class_name) => {
+                write!(formatter, "WiSARD Error: Class {} can't be merged into itself!", class_name)
+            }
+            VocabularyError::NoSamples(ref class_name) => {
+                write!(formatter, "WiSARD Error: Class {} can't be split without samples!", class_name)
+            }
+        }
+    }
+}
+
+impl Error for VocabularyError {}
+
+/// Answer of the WiSARD for a retina
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prediction {
+    /// Same answer as `classify`
+    pub class_name: String,
+    pub probability: f64,
+    pub confidence: f64,
+    /// Classes sharing the highest non zero probability sorted by name, empty without a tie
+    ///
+    /// A tie goes to the first of them, though its zero confidence usually means no decision.
+    pub tied_classes: Vec<String>,
+    /// Probability of every class, after bleaching
+    pub probabilities: HashMap<String, f64>,
+}
+
+fn tied_classes(classifications_probabilities: &HashMap<String, f64>) -> Vec<String> {
+    let max = classifications_probabilities.values().cloned().fold(0f64, f64::max);
+    let mut tied_classes: Vec<String> = classifications_probabilities.iter()
+                                                                     .filter(|&(_, probability)| max > 0f64 && *probability == max)
+                                                                     .map(|(class_name, _)| class_name.clone())
+                                                                     .collect();
+    if tied_classes.len() < 2usize {
+        return Vec::new();
+    }
+    tied_classes.sort();
+    tied_classes
+}
+
+/// Votes of one class for a retina
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassVotes {
+    pub class_name: String,
+    /// Value each ram holds for the address the retina selects
+    pub ram_values: Vec<u64>,
+    /// Rams whose value is above the bleaching threshold
+    pub voting_rams: Vec<usize>,
+    pub probability: f64,
+}
+
+/// Everything the WiSARD looked at to classify a retina
+#[derive(Clone, Debug, PartialEq)]
+pub struct WisardExplanation {
+    /// Same answer as `classify`
+    pub classification: String,
+    /// Classes from the most to the least probable
+    pub classes: Vec<ClassVotes>,
+    /// A ram votes when its value is above it, zero unless bleaching had to raise it
+    pub bleaching_threshold: u64,
+    pub confidence: f64,
+    /// Retina positions read by each ram
+    pub ram_retina_positions: Vec<Vec<usize>>,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Wisard {
     retina_size: usize,
     ram_num_bits: usize,
     use_bleaching: bool,
     minimum_confidence: f64,
     bleaching_threshold: u64,
+    bleaching_policy: BleachingPolicy,
     is_cumulative: bool,
-    discriminators: HashMap<String, Discriminator>,
+    randomize_positions: bool,
+    counter_width: CounterWidth,
+    clustering: Option<Clustering>,
+    discriminators: HashMap<String, ClassDiscriminators>,
     ram_address_mapping: Rc<Vec<usize>>,
 }
 
@@ -44,29 +179,256 @@ impl Wisard {
             use_bleaching,
             minimum_confidence,
             bleaching_threshold,
+            bleaching_policy: BleachingPolicy::MinimumConfidence,
             is_cumulative,
+            randomize_positions,
+            counter_width: CounterWidth::U32,
+            clustering: None,
             discriminators,
             ram_address_mapping,
         }
     }
 
+    /// Wisard with the same settings and address mapping that learned nothing yet
+    pub fn untrained_copy(&self) -> Wisard {
+        Wisard {
+            retina_size: self.retina_size,
+            ram_num_bits: self.ram_num_bits,
+            use_bleaching: self.use_bleaching,
+            minimum_confidence: self.minimum_confidence,
+            bleaching_threshold: self.bleaching_threshold,
+            bleaching_policy: self.bleaching_policy,
+            is_cumulative: self.is_cumulative,
+            randomize_positions: self.randomize_positions,
+            counter_width: self.counter_width,
+            clustering: self.clustering,
+            discriminators: HashMap::new(),
+            ram_address_mapping: self.ram_address_mapping.clone(),
+        }
+    }
+
+    /// Regression WiSARD reading retinas with rams of as many bits and the same address mapping
+    pub fn untrained_regression_wisard(&self) -> RegressionWisard {
+        RegressionWisard::new(self.ram_num_bits, &self.ram_address_mapping)
+    }
+
+    /// Shares the address mapping with every discriminator again after a load
+    pub fn restore(&mut self) {
+        for discriminator in self.discriminators.values_mut() {
+            discriminator.set_ram_address_mapping(&self.ram_address_mapping);
+        }
+    }
+
+    /// Shuffles the ram address mapping from a seed, which must be done before training and does
+    /// nothing when positions aren't randomized
+    pub fn set_seed(&mut self, seed: usize) {
+        if !self.discriminators.is_empty() {
+            panic!("WiSARD Error: Address mapping can't change after training!");
+        }
+        if self.randomize_positions {
+            let mut ram_address_mapping = (0..self.retina_size).collect::<Vec<usize>>();
+            let mut random_generator: StdRng = SeedableRng::from_seed(&[seed][..]);
+            random_generator.shuffle(&mut ram_address_mapping);
+            self.ram_address_mapping = Rc::new(ram_address_mapping);
+        }
+    }
+
+    /// Selects how bleaching picks its threshold, only used when bleaching is enabled
+    pub fn set_bleaching_policy(&mut self, bleaching_policy: BleachingPolicy) {
+        self.bleaching_policy = bleaching_policy;
+    }
+
+    /// Selects the width of the saturating ram counters, which must be done before training
+    pub fn set_counter_width(&mut self, counter_width: CounterWidth) {
+        if !self.discriminators.is_empty() {
+            panic!("WiSARD Error: Counter width can't change after training!");
+        }
+        self.counter_width = counter_width;
+    }
+
+    /// Lets classes learn with several discriminators, which must be done before training
+    pub fn set_clustering(&mut self, clustering: Option<Clustering>) {
+        if !self.discriminators.is_empty() {
+            panic!("WiSARD Error: Clustering can't change after training!");
+        }
+        if let Some(clustering) = clustering {
+            if clustering.max_discriminators == 0usize {
+                panic!("WiSARD Error: Clustering needs at least one discriminator per class!");
+            }
+        }
+        self.clustering = clustering;
+    }
+
+    /// Heap memory taken by the rams of every class, sorted by class name
+    pub fn class_memory_bytes(&self) -> Vec<(String, usize)> {
+        let mut memory: Vec<(String, usize)> = self.discriminators
+                                                   .iter()
+                                                   .map(|(class_name, discriminator)| (class_name.clone(), discriminator.memory_bytes()))
+                                                   .collect();
+        memory.sort_by(|first, second| first.0.cmp(&second.0));
+        memory
+    }
+
+    pub fn statistics(&self) -> WisardStatistics {
+        let mut classes: Vec<ClassStatistics> = self.discriminators
+                                                    .iter()
+                                                    .map(|(class_name, discriminator)| discriminator.statistics(class_name))
+                                                    .collect();
+        classes.sort_by(|first, second| first.class_name.cmp(&second.class_name));
+
+        WisardStatistics::new(classes, self.memory_bytes())
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.discriminators.values().map(|discriminator| discriminator.memory_bytes()).sum::<usize>()
+            + self.ram_address_mapping.len() * mem::size_of::<usize>()
+    }
+
     pub fn train(&mut self, class_name: String, retina: &[bool]) {
+        let retina_size = self.retina_size;
+        let ram_num_bits = self.ram_num_bits;
+        let ram_address_mapping = &self.ram_address_mapping;
+        let is_cumulative = self.is_cumulative;
+        let counter_width = self.counter_width;
+        let new_discriminator = || Discriminator::new(
+            retina_size,
+            ram_num_bits,
+            ram_address_mapping.clone(),
+            is_cumulative,
+            counter_width,
+        );
+
         // Checking if class name exist before creating a new one
-        self.discriminators.entry(class_name).or_insert(Discriminator::new(
-            self.retina_size,
-            self.ram_num_bits,
-            self.ram_address_mapping.clone(),
-            self.is_cumulative,
-        )).train(retina);
+        match self.discriminators.entry(class_name) {
+            Entry::Occupied(mut entry) => entry.get_mut().train(retina, self.clustering, new_discriminator),
+            Entry::Vacant(entry) => {
+                let mut discriminator = new_discriminator();
+                discriminator.train(retina);
+                entry.insert(ClassDiscriminators::new(discriminator));
+            }
+        }
     }
 
-    pub fn forget(&mut self, class_name: &str, retina: &[bool]) {
-        if let Some(discriminator) = self.discriminators.get_mut(class_name) {
-            discriminator.forget(retina);
+    /// Removes a trained retina from a class
+    ///
+    /// Without cumulative rams, retinas sharing an address with an already forgotten one can't be
+    /// told apart from untrained ones.
+    pub fn forget(&mut self, class_name: &str, retina: &[bool]) -> Result<(), ForgetError> {
+        let discriminator = match self.discriminators.get_mut(class_name) {
+            Some(discriminator) => discriminator,
+            None => return Err(ForgetError::UnknownClass(String::from(class_name))),
+        };
+
+        if discriminator.trained_examples() == 0u64 {
+            return Err(ForgetError::NoTrainedExamples(String::from(class_name)));
+        }
+        if !discriminator.forget(retina) {
+            return Err(ForgetError::UntrainedSample(String::from(class_name)));
         }
+        Ok(())
+    }
+
+    /// Deletes a class and everything it learned, returns whether it existed
+    pub fn remove_class(&mut self, class_name: &str) -> bool {
+        self.discriminators.remove(class_name).is_some()
+    }
+
+    pub fn has_class(&self, class_name: &str) -> bool {
+        self.discriminators.contains_key(class_name)
+    }
+
+    /// Examples trained minus examples forgotten of every class, sorted by class name
+    pub fn class_examples(&self) -> Vec<(String, u64)> {
+        let mut examples: Vec<(String, u64)> = self.discriminators
+                                                   .iter()
+                                                   .map(|(class_name, discriminators)| (class_name.clone(), discriminators.trained_examples()))
+                                                   .collect();
+        examples.sort_by(|first, second| first.0.cmp(&second.0));
+        examples
+    }
+
+    /// Gives a class a new name, keeping everything it learned
+    pub fn rename_class(&mut self, class_name: &str, new_class_name: &str) -> Result<(), VocabularyError> {
+        if self.discriminators.contains_key(new_class_name) {
+            return Err(VocabularyError::ClassExists(String::from(new_class_name)));
+        }
+        match self.discriminators.remove(class_name) {
+            Some(discriminators) => {
+                self.discriminators.insert(String::from(new_class_name), discriminators);
+                Ok(())
+            }
+            None => Err(VocabularyError::UnknownClass(String::from(class_name))),
+        }
+    }
+
+    /// Adds everything a class learned to another one and deletes it
+    ///
+    /// Clustered classes keep the merged discriminators apart up to the clustering limit.
+    pub fn merge_classes(&mut self, class_name: &str, into_class_name: &str, mode: RamMerge) -> Result<(), VocabularyError> {
+        if class_name == into_class_name {
+            return Err(VocabularyError::SameClass(String::from(class_name)));
+        }
+        if !self.discriminators.contains_key(into_class_name) {
+            return Err(VocabularyError::UnknownClass(String::from(into_class_name)));
+        }
+        let discriminators = match self.discriminators.remove(class_name) {
+            Some(discriminators) => discriminators,
+            None => return Err(VocabularyError::UnknownClass(String::from(class_name))),
+        };
+
+        let max_discriminators = self.clustering.map_or(1usize, |clustering| clustering.max_discriminators);
+        self.discriminators.get_mut(into_class_name).unwrap().merge(discriminators, mode, max_discriminators);
+        Ok(())
     }
 
     pub fn classification_probabilities(&self, retina: &[bool]) -> HashMap<String, f64> {
+        let (results, _, _) = self.scores(retina);
+        results
+    }
+
+    /// Breaks a classification down into the votes of every ram of every class
+    pub fn explain(&self, retina: &[bool]) -> WisardExplanation {
+        let (results, rams_results, bleaching_threshold) = self.scores(retina);
+        let (confidence, (classification, _)) = self.decide(&results);
+
+        let mut classes: Vec<ClassVotes> = rams_results.into_iter().map(
+            |(class_name, ram_values)| {
+                let voting_rams = ram_values.iter()
+                                            .enumerate()
+                                            .filter(|&(_, value)| *value > bleaching_threshold)
+                                            .map(|(ram_index, _)| ram_index)
+                                            .collect();
+                ClassVotes {
+                    probability: results[&class_name],
+                    class_name,
+                    ram_values,
+                    voting_rams,
+                }
+            }
+        ).collect();
+        classes.sort_by(
+            |first, second|
+                second.probability
+                      .partial_cmp(&first.probability)
+                      .unwrap()
+                      .then_with(|| first.class_name.cmp(&second.class_name))
+        );
+
+        WisardExplanation {
+            classification,
+            classes,
+            bleaching_threshold,
+            confidence,
+            ram_retina_positions: discriminator::ram_retina_positions(
+                self.retina_size,
+                self.ram_num_bits,
+                &self.ram_address_mapping,
+            ),
+        }
+    }
+
+    /// Probabilities and ram values of every class, with the bleaching threshold they were computed at
+    fn scores(&self, retina: &[bool]) -> (HashMap<String, f64>, HashMap<String, Vec<u64>>, u64) {
         let mut results = HashMap::with_capacity(self.discriminators.len());
         let mut rams_results = HashMap::with_capacity(self.discriminators.len());
 
@@ -83,11 +445,24 @@ impl Wisard {
             rams_results.insert(class_name.clone(), ram_result);
         }
 
+        let mut bleaching_threshold = 0u64;
         if self.use_bleaching {
-            results = self.apply_bleaching(results, &rams_results, rams_count);
+            let (bleached_results, reached_threshold) = self.apply_bleaching(results, &rams_results, rams_count);
+            results = bleached_results;
+            bleaching_threshold = reached_threshold;
         }
 
-        results
+        (results, rams_results, bleaching_threshold)
+    }
+
+    /// Retina hit counts of every class, sorted by class name
+    pub fn class_retina_hit_counts(&self) -> Vec<(String, Vec<u64>)> {
+        let mut hit_counts: Vec<(String, Vec<u64>)> = self.discriminators
+                                                          .iter()
+                                                          .map(|(class_name, discriminator)| (class_name.clone(), discriminator.retina_hit_counts()))
+                                                          .collect();
+        hit_counts.sort_by(|first, second| first.0.cmp(&second.0));
+        hit_counts
     }
 
     pub fn classify(&self, retina: &[bool]) -> String {
@@ -101,71 +476,341 @@ impl Wisard {
     }
 
     pub fn classification_confidence_and_probability(&self, retina: &[bool]) -> (f64, (String, f64)) {
-        let (confidence, best_class) = self.calculate_confidence(&self.classification_probabilities(retina));
+        self.decide(&self.classification_probabilities(retina))
+    }
+
+    /// Classification with its confidence and the classes tied for the highest probability
+    pub fn predict(&self, retina: &[bool]) -> Prediction {
+        let probabilities = self.classification_probabilities(retina);
+        let (confidence, (class_name, probability)) = self.decide(&probabilities);
+
+        Prediction {
+            class_name,
+            probability,
+            confidence,
+            tied_classes: tied_classes(&probabilities),
+            probabilities,
+        }
+    }
+
+    fn decide(&self, classifications_probabilities: &HashMap<String, f64>) -> (f64, (String, f64)) {
+        let (confidence, best_class) = self.calculate_confidence(classifications_probabilities);
         if confidence < self.minimum_confidence {
             return (0f64, (String::from("Not enough confidence to decide"), 0f64))
         }
         (confidence, best_class)
     }
 
+    /// Bleached probabilities and the threshold ram values had to exceed, zero for unbleached ones
     fn apply_bleaching(
         &self,
         results: HashMap<String, f64>,
         rams_results: &HashMap<String, Vec<u64>>,
         rams_count: f64
-    ) -> HashMap<String, f64> {
-        let mut bleached_results = results.clone();
-        let (mut confidence, _) = self.calculate_confidence(&results);
-        let mut current_bleaching_threshold = self.bleaching_threshold;
-
-        while confidence < self.minimum_confidence {
-            let mut max_value = 0f64;
+    ) -> (HashMap<String, f64>, u64) {
+        let sorted_values = SortedRamValues::new(rams_results, rams_count);
 
-            for (class_name, result) in &mut bleached_results {
-                let summed_ram_values = rams_results[class_name]
-                    .iter()
-                    .filter(|&value| *value > current_bleaching_threshold )
-                    .count();
+        match self.bleaching_policy {
+            BleachingPolicy::MinimumConfidence => {
+                if self.calculate_confidence(&results).0 >= self.minimum_confidence {
+                    return (results, 0u64);
+                }
 
-                *result = summed_ram_values as f64 / rams_count;
+                let thresholds: Vec<u64> = sorted_values.thresholds_from(self.bleaching_threshold).collect();
+                // Votes only fall as the threshold rises, so thresholds past the first one without
+                // any vote have none either
+                let voting_thresholds = &thresholds[..thresholds.partition_point(|&threshold| !bleaching::no_votes(&sorted_values.probabilities(threshold)))];
+                let confident_index = voting_thresholds.partition_point(
+                    |&threshold| self.calculate_confidence(&sorted_values.probabilities(threshold)).0 < self.minimum_confidence
+                );
 
-                if *result - max_value > 0.0001 {
-                    max_value = *result;
+                // If no ram recognizes the pattern at a confident threshold, return previous results
+                match voting_thresholds.get(confident_index) {
+                    Some(&threshold) => (sorted_values.probabilities(threshold), threshold),
+                    None => (results, 0u64),
                 }
             }
+            BleachingPolicy::Fixed { threshold } => (sorted_values.probabilities(threshold), threshold),
+            BleachingPolicy::SingleWinner => {
+                if bleaching::has_single_winner(&results) {
+                    return (results, 0u64);
+                }
 
-            // If no ram recognizes the pattern, return previous results
-            if max_value <= 0.000_001 {
-                return results;
+                let mut last_results = (results, 0u64);
+                for threshold in sorted_values.thresholds_from(self.bleaching_threshold) {
+                    let bleached_results = sorted_values.probabilities(threshold);
+                    if bleaching::no_votes(&bleached_results) {
+                        break;
+                    }
+                    if bleaching::has_single_winner(&bleached_results) {
+                        return (bleached_results, threshold);
+                    }
+                    last_results = (bleached_results, threshold);
+                }
+                last_results
+            }
+            BleachingPolicy::MaximalConfidence => {
+                let mut best_confidence = self.calculate_confidence(&results).0;
+                let mut best_results = (results, 0u64);
+                for threshold in sorted_values.thresholds_from(self.bleaching_threshold) {
+                    let bleached_results = sorted_values.probabilities(threshold);
+                    if bleaching::no_votes(&bleached_results) {
+                        break;
+                    }
+                    let confidence = self.calculate_confidence(&bleached_results).0;
+                    if confidence > best_confidence {
+                        best_confidence = confidence;
+                        best_results = (bleached_results, threshold);
+                    }
+                }
+                best_results
             }
-
-            current_bleaching_threshold += 1u64;
-            confidence = self.calculate_confidence(&bleached_results).0;
         }
-
-        bleached_results
     }
 
+    /// Confidence and best class, a tie going to the class whose name comes first
+    ///
+    /// When every probability is zero no class wins and the best class name is empty.
     fn calculate_confidence(
         &self,
         classifications_probabilities: &HashMap<String, f64>,
     ) -> (f64, (String, f64)) {
-        let mut best_class_name = &String::new();
+        let mut best_class_name = "";
         let mut max = 0f64;
         let mut second_max = 0f64;
 
         for (class_name, probability) in classifications_probabilities.iter() {
-            if max < *probability {
+            if *probability > max || (*probability == max && max > 0f64 && class_name.as_str() < best_class_name) {
                 second_max = max;
                 max = *probability;
                 best_class_name = class_name;
-            } else if second_max < *probability {
+            } else if *probability > second_max {
                 second_max = *probability;
             }
         }
 
         let confidence = if max != 0f64 { 1f64 - (second_max) / max } else { 0f64 };
 
-        (confidence, (best_class_name.clone(), max))
+        (confidence, (String::from(best_class_name), max))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::rand::{SeedableRng, StdRng};
+
+    /// Bleaching as it was done before thresholds were searched over sorted values
+    fn linear_bleaching(
+        wisard: &Wisard,
+        results: HashMap<String, f64>,
+        rams_results: &HashMap<String, Vec<u64>>,
+        rams_count: f64
+    ) -> HashMap<String, f64> {
+        let mut bleached_results = results.clone();
+        let (mut confidence, _) = wisard.calculate_confidence(&results);
+        let mut current_bleaching_threshold = wisard.bleaching_threshold;
+
+        while confidence < wisard.minimum_confidence {
+            let mut max_value = 0f64;
+            for (class_name, result) in &mut bleached_results {
+                let summed_ram_values = rams_results[class_name].iter().filter(|&value| *value > current_bleaching_threshold).count();
+                *result = summed_ram_values as f64 / rams_count;
+                if *result - max_value > 0.0001 {
+                    max_value = *result;
+                }
+            }
+            if max_value <= 0.000_001 {
+                return results;
+            }
+            current_bleaching_threshold += 1u64;
+            confidence = wisard.calculate_confidence(&bleached_results).0;
+        }
+
+        bleached_results
+    }
+
+    fn probabilities(classes: &[(&str, f64)]) -> HashMap<String, f64> {
+        classes.iter().map(|&(class_name, probability)| (String::from(class_name), probability)).collect()
+    }
+
+    #[test]
+    fn ties_go_to_the_first_class_name() {
+        let wisard = Wisard::new(8usize, 2usize, false, 0f64, 1u64, false, true);
+
+        for _ in 0..20 {
+            let tied = probabilities(&[("c", 0.5f64), ("b", 0.5f64), ("a", 0.25f64), ("d", 0.5f64)]);
+            assert_eq!(wisard.calculate_confidence(&tied), (0f64, (String::from("b"), 0.5f64)));
+            assert_eq!(tied_classes(&tied), vec![String::from("b"), String::from("c"), String::from("d")]);
+        }
+
+        let untied = probabilities(&[("a", 0.25f64), ("b", 0.5f64), ("c", 0.4f64)]);
+        let (confidence, best_class) = wisard.calculate_confidence(&untied);
+        assert!((confidence - 0.2f64).abs() < 1e-12);
+        assert_eq!(best_class, (String::from("b"), 0.5f64));
+        assert!(tied_classes(&untied).is_empty());
+    }
+
+    #[test]
+    fn clustering_adds_discriminators_up_to_the_limit() {
+        let mut wisard = Wisard::new(8usize, 2usize, false, 0f64, 1u64, false, true);
+        wisard.set_clustering(Some(Clustering { score_threshold: 0.5f64, max_discriminators: 2usize }));
+        let first = [true; 8];
+        let second = [false; 8];
+        let third = [true, false, true, false, true, false, true, false];
+
+        wisard.train(String::from("a"), &first);
+        wisard.train(String::from("a"), &first);
+        wisard.train(String::from("a"), &second);
+        wisard.train(String::from("a"), &third);
+        wisard.train(String::from("b"), &third);
+
+        let statistics = wisard.statistics();
+        assert_eq!(statistics.classes[0].discriminators_count, 2usize);
+        assert_eq!(statistics.classes[0].trained_examples, 4u64);
+        assert_eq!(statistics.classes[1].discriminators_count, 1usize);
+
+        // Each retina is fully recognised by the discriminator that learned it
+        assert_eq!(wisard.classification_probabilities(&first)["a"], 1f64);
+        assert_eq!(wisard.classification_probabilities(&second)["a"], 1f64);
+        assert_eq!(wisard.classify(&first), "a");
+
+        assert_eq!(wisard.forget("a", &second), Ok(()));
+        assert_eq!(wisard.forget("a", &second), Err(ForgetError::UntrainedSample(String::from("a"))));
+    }
+
+    #[test]
+    fn merging_classes_or_and_sum() {
+        let first = [true, true, false, false];
+        let second = [false, false, true, true];
+
+        for &is_cumulative in &[true, false] {
+            for &mode in &[RamMerge::Or, RamMerge::Sum] {
+                let mut wisard = Wisard::new(4usize, 2usize, false, 0f64, 1u64, false, is_cumulative);
+                wisard.train(String::from("a"), &first);
+                wisard.train(String::from("a"), &first);
+                wisard.train(String::from("b"), &first);
+                wisard.train(String::from("b"), &second);
+
+                assert_eq!(wisard.merge_classes("b", "b", mode), Err(VocabularyError::SameClass(String::from("b"))));
+                assert_eq!(wisard.merge_classes("c", "a", mode), Err(VocabularyError::UnknownClass(String::from("c"))));
+                wisard.merge_classes("b", "a", mode).unwrap();
+                let merged_examples = if is_cumulative && mode == RamMerge::Or { 2u64 } else { 4u64 };
+                assert_eq!(wisard.class_examples(), vec![(String::from("a"), merged_examples)]);
+
+                let explanation = wisard.explain(&first);
+                let expected = match (is_cumulative, mode) {
+                    (false, _) => vec![1u64, 1u64],
+                    (true, RamMerge::Or) => vec![2u64, 2u64],
+                    (true, RamMerge::Sum) => vec![3u64, 3u64],
+                };
+                assert_eq!(explanation.classes[0].ram_values, expected);
+                assert_eq!(wisard.explain(&second).classes[0].ram_values, vec![1u64, 1u64]);
+            }
+        }
+    }
+
+    #[test]
+    fn merging_clustered_classes_keeps_their_discriminators_up_to_the_limit() {
+        let first = [true; 8];
+        let second = [false; 8];
+        let third = [true, true, true, true, true, true, false, false];
+
+        let mut wisard = Wisard::new(8usize, 2usize, false, 0f64, 1u64, false, true);
+        wisard.set_clustering(Some(Clustering { score_threshold: 0.5f64, max_discriminators: 2usize }));
+        wisard.train(String::from("a"), &first);
+        wisard.train(String::from("b"), &second);
+        wisard.train(String::from("b"), &third);
+        assert_eq!(wisard.statistics().classes[1].discriminators_count, 2usize);
+
+        wisard.merge_classes("b", "a", RamMerge::Sum).unwrap();
+        let statistics = wisard.statistics();
+        assert_eq!(statistics.classes[0].discriminators_count, 2usize);
+        assert_eq!(statistics.classes[0].trained_examples, 3u64);
+
+        // The second discriminator of b joins the most similar cluster, the one trained with first
+        assert_eq!(wisard.classification_probabilities(&second)["a"], 1f64);
+        assert_eq!(wisard.explain(&first).classes[0].ram_values, vec![2u64, 2u64, 2u64, 1u64]);
+        assert_eq!(wisard.explain(&third).classes[0].ram_values, vec![2u64, 2u64, 2u64, 1u64]);
+    }
+
+    #[test]
+    fn renaming_keeps_what_a_class_learned() {
+        let mut wisard = Wisard::new(4usize, 2usize, false, 0f64, 1u64, false, true);
+        wisard.train(String::from("a"), &[true, false, true, false]);
+        wisard.train(String::from("b"), &[false, true, false, true]);
+
+        assert_eq!(wisard.rename_class("a", "b"), Err(VocabularyError::ClassExists(String::from("b"))));
+        assert_eq!(wisard.rename_class("c", "d"), Err(VocabularyError::UnknownClass(String::from("c"))));
+        wisard.rename_class("a", "c").unwrap();
+        assert_eq!(wisard.classify(&[true, false, true, false]), "c");
+        assert_eq!(wisard.class_examples(), vec![(String::from("b"), 1u64), (String::from("c"), 1u64)]);
+    }
+
+    #[test]
+    fn single_class_and_empty_model() {
+        let mut wisard = Wisard::new(8usize, 2usize, true, 0.1f64, 1u64, false, true);
+        let retina = [true, false, true, true, false, false, true, false];
+
+        let prediction = wisard.predict(&retina);
+        assert_eq!(prediction.confidence, 0f64);
+        assert_eq!(prediction.class_name, "Not enough confidence to decide");
+        assert!(prediction.tied_classes.is_empty());
+
+        wisard.train(String::from("only"), &retina);
+        let prediction = wisard.predict(&retina);
+        assert_eq!(prediction, Prediction {
+            class_name: String::from("only"),
+            probability: 1f64,
+            confidence: 1f64,
+            tied_classes: Vec::new(),
+            probabilities: probabilities(&[("only", 1f64)]),
+        });
+    }
+
+    /// Whether confidence never falls as the threshold rises while some ram still votes
+    fn confidence_grows_with_threshold(wisard: &Wisard, rams_results: &HashMap<String, Vec<u64>>, rams_count: f64) -> bool {
+        let sorted_values = SortedRamValues::new(rams_results, rams_count);
+        let confidences: Vec<f64> = sorted_values.thresholds_from(wisard.bleaching_threshold)
+                                                 .map(|threshold| sorted_values.probabilities(threshold))
+                                                 .take_while(|probabilities| !bleaching::no_votes(probabilities))
+                                                 .map(|probabilities| wisard.calculate_confidence(&probabilities).0)
+                                                 .collect();
+        confidences.windows(2usize).all(|pair| pair[0] <= pair[1])
+    }
+
+    /// Bisecting finds the threshold the linear search finds whenever confidence grows with the
+    /// threshold, and otherwise still one reaching the minimum confidence
+    #[test]
+    fn default_bleaching_matches_linear_search() {
+        let mut random_generator: StdRng = SeedableRng::from_seed(&[11usize][..]);
+        let rams_count = 16usize;
+        let mut growing_cases = 0usize;
+
+        for minimum_confidence in &[0.002f64, 0.1f64, 0.5f64] {
+            let wisard = Wisard::new(64usize, 4usize, true, *minimum_confidence, 1u64, false, true);
+            for _ in 0..200 {
+                let mut rams_results = HashMap::new();
+                let mut results = HashMap::new();
+                for class_index in 0..5usize {
+                    let ram_values: Vec<u64> = (0..rams_count).map(|_| random_generator.gen_range(0u64, 12u64)).collect();
+                    let votes = ram_values.iter().filter(|&value| *value > 0u64).count();
+                    results.insert(class_index.to_string(), votes as f64 / rams_count as f64);
+                    rams_results.insert(class_index.to_string(), ram_values);
+                }
+
+                let expected = linear_bleaching(&wisard, results.clone(), &rams_results, rams_count as f64);
+                let (bleached_results, threshold) = wisard.apply_bleaching(results.clone(), &rams_results, rams_count as f64);
+                if confidence_grows_with_threshold(&wisard, &rams_results, rams_count as f64) {
+                    growing_cases += 1usize;
+                    assert_eq!(bleached_results, expected);
+                } else if threshold == 0u64 {
+                    assert_eq!(bleached_results, results);
+                } else {
+                    assert!(wisard.calculate_confidence(&bleached_results).0 >= *minimum_confidence);
+                }
+            }
+        }
+        assert!(growing_cases > 0usize);
+    }
+}
@@ -1,15 +1,25 @@
-use std::collections::hash_map::HashMap;
+use super::ram_storage::{CounterWidth, RamStorage};
 
+/// How the rams of two classes are combined when merging them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RamMerge {
+    /// An address is stored when either ram stored it, cumulative rams keeping the larger count
+    Or,
+    /// Counts of both rams are added, saturating, which is the same as Or for non cumulative rams
+    Sum,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Ram {
-    data: HashMap<usize, u64>,
+    data: RamStorage,
     max_address: usize,
     is_cumulative: bool,
 }
 
 impl Ram {
-    pub fn new(num_bits: usize, is_cumulative: bool) -> Ram {
+    pub fn new(num_bits: usize, is_cumulative: bool, counter_width: CounterWidth) -> Ram {
         let max_address = 2usize.pow(num_bits as u32);
-        let data = HashMap::new();
+        let data = RamStorage::new(num_bits, counter_width);
 
         Ram {
             data,
@@ -24,9 +34,10 @@ impl Ram {
         }
 
         if !self.is_cumulative {
-            self.data.insert(address, 1);
+            self.data.set(address, 1);
         } else {
-            *self.data.entry(address).or_insert(0) += 1;
+            let value = self.data.get(address);
+            self.data.set(address, value.saturating_add(1));
         }
     }
 
@@ -36,23 +47,51 @@ impl Ram {
         }
 
         if !self.is_cumulative {
-            self.data.insert(address, 0);
+            self.data.set(address, 0);
         } else {
-            self.data.entry(address).and_modify(
-                |val|
-                    if *val > 0 {
-                        *val -= 1
-                    } else {
-                        *val = 0
-                    }
-            ).or_insert(0);
+            let value = self.data.get(address);
+            self.data.set(address, value.saturating_sub(1));
         }
     }
 
-    pub fn get(&self, address: usize) -> u64 {
-        match self.data.get(&address) {
-            Some(value) => *value,
-            None => 0u64,
+    /// Adds the addresses stored by another ram of the same size
+    pub fn merge(&mut self, other: &Ram, mode: RamMerge) {
+        for (address, value) in other.entries().filter(|&(_, value)| value > 0u64) {
+            let merged = if !self.is_cumulative {
+                1u64
+            } else {
+                match mode {
+                    RamMerge::Or => self.data.get(address).max(value),
+                    RamMerge::Sum => self.data.get(address).saturating_add(value),
+                }
+            };
+            self.data.set(address, merged);
         }
     }
+
+    pub fn is_cumulative(&self) -> bool {
+        self.is_cumulative
+    }
+
+    /// Addresses holding a value, in no particular order
+    pub fn entries<'a>(&'a self) -> Box<dyn Iterator<Item=(usize, u64)> + 'a> {
+        self.data.entries()
+    }
+
+    pub fn get(&self, address: usize) -> u64 {
+        self.data.get(address)
+    }
+
+    /// Number of addresses the ram can be asked for
+    pub fn address_space(&self) -> usize {
+        self.max_address
+    }
+
+    pub fn counter_max(&self) -> u64 {
+        self.data.counter_max()
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.data.memory_bytes()
+    }
 }
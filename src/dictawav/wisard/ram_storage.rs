@@ -0,0 +1,331 @@
+use std::mem;
+
+/// Widest address for which a ram keeps a counter for every address
+pub const DENSE_MAX_NUM_BITS: usize = 10;
+
+const EMPTY_KEY: usize = usize::MAX;
+const HASH_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Bit width of the saturating counters kept by the rams
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterWidth {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl CounterWidth {
+    pub fn max_value(self) -> u64 {
+        match self {
+            CounterWidth::U8 => u64::from(u8::MAX),
+            CounterWidth::U16 => u64::from(u16::MAX),
+            CounterWidth::U32 => u64::from(u32::MAX),
+            CounterWidth::U64 => u64::MAX,
+        }
+    }
+}
+
+/// Counters of a single width, values above it are never stored
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CounterArray {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl CounterArray {
+    fn new(width: CounterWidth, len: usize) -> CounterArray {
+        match width {
+            CounterWidth::U8 => CounterArray::U8(vec![0u8; len]),
+            CounterWidth::U16 => CounterArray::U16(vec![0u16; len]),
+            CounterWidth::U32 => CounterArray::U32(vec![0u32; len]),
+            CounterWidth::U64 => CounterArray::U64(vec![0u64; len]),
+        }
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> u64 {
+        match *self {
+            CounterArray::U8(ref counters) => u64::from(counters[index]),
+            CounterArray::U16(ref counters) => u64::from(counters[index]),
+            CounterArray::U32(ref counters) => u64::from(counters[index]),
+            CounterArray::U64(ref counters) => counters[index],
+        }
+    }
+
+    /// The value must fit the counter width
+    #[inline]
+    fn set(&mut self, index: usize, value: u64) {
+        match *self {
+            CounterArray::U8(ref mut counters) => counters[index] = value as u8,
+            CounterArray::U16(ref mut counters) => counters[index] = value as u16,
+            CounterArray::U32(ref mut counters) => counters[index] = value as u32,
+            CounterArray::U64(ref mut counters) => counters[index] = value,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            CounterArray::U8(ref counters) => counters.len(),
+            CounterArray::U16(ref counters) => counters.len(),
+            CounterArray::U32(ref counters) => counters.len(),
+            CounterArray::U64(ref counters) => counters.len(),
+        }
+    }
+
+    fn width(&self) -> CounterWidth {
+        match *self {
+            CounterArray::U8(_) => CounterWidth::U8,
+            CounterArray::U16(_) => CounterWidth::U16,
+            CounterArray::U32(_) => CounterWidth::U32,
+            CounterArray::U64(_) => CounterWidth::U64,
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        match *self {
+            CounterArray::U8(ref counters) => counters.capacity() * mem::size_of::<u8>(),
+            CounterArray::U16(ref counters) => counters.capacity() * mem::size_of::<u16>(),
+            CounterArray::U32(ref counters) => counters.capacity() * mem::size_of::<u32>(),
+            CounterArray::U64(ref counters) => counters.capacity() * mem::size_of::<u64>(),
+        }
+    }
+}
+
+/// Open addressing map with linear probing, keys and counters kept in separate arrays
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddressTable {
+    keys: Vec<usize>,
+    counters: CounterArray,
+    len: usize,
+}
+
+impl AddressTable {
+    fn new(width: CounterWidth) -> AddressTable {
+        AddressTable {
+            keys: Vec::new(),
+            counters: CounterArray::new(width, 0usize),
+            len: 0usize,
+        }
+    }
+
+    /// Slot where probing for the address starts
+    #[inline]
+    fn home_slot(&self, address: usize) -> usize {
+        let hash = (address as u64).wrapping_mul(HASH_MULTIPLIER);
+        (hash >> (64u32 - self.keys.len().trailing_zeros())) as usize & (self.keys.len() - 1usize)
+    }
+
+    /// Slot holding the address, or the empty slot where it would go
+    fn find(&self, address: usize) -> Result<usize, usize> {
+        let mask = self.keys.len() - 1usize;
+        let mut slot = self.home_slot(address);
+
+        loop {
+            match self.keys[slot] {
+                key if key == address => return Ok(slot),
+                EMPTY_KEY => return Err(slot),
+                _ => slot = (slot + 1usize) & mask,
+            }
+        }
+    }
+
+    fn get(&self, address: usize) -> u64 {
+        if self.len == 0usize {
+            return 0u64;
+        }
+        match self.find(address) {
+            Ok(slot) => self.counters.get(slot),
+            Err(_) => 0u64,
+        }
+    }
+
+    fn set(&mut self, address: usize, value: u64) {
+        // Keeping the load factor under 3/4
+        if (self.len + 1usize) * 4usize > self.keys.len() * 3usize {
+            self.grow();
+        }
+
+        match self.find(address) {
+            Ok(slot) => self.counters.set(slot, value),
+            Err(slot) => {
+                self.keys[slot] = address;
+                self.counters.set(slot, value);
+                self.len += 1usize;
+            }
+        }
+    }
+
+    /// Empties the slot of an address, moving back the addresses probed past it
+    fn remove(&mut self, address: usize) {
+        if self.len == 0usize {
+            return;
+        }
+        let mut hole = match self.find(address) {
+            Ok(slot) => slot,
+            Err(_) => return,
+        };
+
+        let mask = self.keys.len() - 1usize;
+        let mut slot = (hole + 1usize) & mask;
+        while self.keys[slot] != EMPTY_KEY {
+            // An address can fill the hole when the hole lies between its home slot and its slot
+            let home_slot = self.home_slot(self.keys[slot]);
+            if (slot.wrapping_sub(home_slot) & mask) >= (slot.wrapping_sub(hole) & mask) {
+                self.keys[hole] = self.keys[slot];
+                let value = self.counters.get(slot);
+                self.counters.set(hole, value);
+                hole = slot;
+            }
+            slot = (slot + 1usize) & mask;
+        }
+
+        self.keys[hole] = EMPTY_KEY;
+        self.counters.set(hole, 0u64);
+        self.len -= 1usize;
+    }
+
+    fn grow(&mut self) {
+        let capacity = (self.keys.len() * 2usize).max(8usize);
+        let old_keys = mem::replace(&mut self.keys, vec![EMPTY_KEY; capacity]);
+        let width = self.counters.width();
+        let old_counters = mem::replace(&mut self.counters, CounterArray::new(width, capacity));
+
+        for (slot, key) in old_keys.into_iter().enumerate() {
+            if key != EMPTY_KEY {
+                let new_slot = self.find(key).unwrap_err();
+                self.keys[new_slot] = key;
+                self.counters.set(new_slot, old_counters.get(slot));
+            }
+        }
+    }
+
+    fn entries<'a>(&'a self) -> impl Iterator<Item=(usize, u64)> + 'a {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|&(_, key)| *key != EMPTY_KEY)
+            .map(move |(slot, key)| (*key, self.counters.get(slot)))
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.keys.capacity() * mem::size_of::<usize>() + self.counters.memory_bytes()
+    }
+}
+
+/// Counters of a ram, dense for narrow addresses and a hash table for wide ones
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RamStorage {
+    Dense(CounterArray),
+    Sparse(AddressTable),
+}
+
+impl RamStorage {
+    pub fn new(num_bits: usize, width: CounterWidth) -> RamStorage {
+        if num_bits <= DENSE_MAX_NUM_BITS {
+            RamStorage::Dense(CounterArray::new(width, 1usize << num_bits))
+        } else {
+            RamStorage::Sparse(AddressTable::new(width))
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, address: usize) -> u64 {
+        match *self {
+            RamStorage::Dense(ref counters) => counters.get(address),
+            RamStorage::Sparse(ref table) => table.get(address),
+        }
+    }
+
+    /// Stores a value, saturating it at the largest counter value, a zero frees the address
+    pub fn set(&mut self, address: usize, value: u64) {
+        match *self {
+            RamStorage::Dense(ref mut counters) => {
+                let value = value.min(counters.width().max_value());
+                counters.set(address, value);
+            }
+            RamStorage::Sparse(ref mut table) => {
+                if value == 0u64 {
+                    table.remove(address);
+                } else {
+                    let value = value.min(table.counters.width().max_value());
+                    table.set(address, value);
+                }
+            }
+        }
+    }
+
+    /// Addresses holding a value, dense storage only reporting non zero counters
+    pub fn entries<'a>(&'a self) -> Box<dyn Iterator<Item=(usize, u64)> + 'a> {
+        match *self {
+            RamStorage::Dense(ref counters) => Box::new(
+                (0..counters.len()).map(move |address| (address, counters.get(address)))
+                                        .filter(|&(_, value)| value > 0u64)
+            ),
+            RamStorage::Sparse(ref table) => Box::new(table.entries()),
+        }
+    }
+
+    /// Largest value a counter holds before saturating
+    pub fn counter_max(&self) -> u64 {
+        match *self {
+            RamStorage::Dense(ref counters) => counters.width().max_value(),
+            RamStorage::Sparse(ref table) => table.counters.width().max_value(),
+        }
+    }
+
+    /// Heap memory taken by the counters and addresses
+    pub fn memory_bytes(&self) -> usize {
+        match *self {
+            RamStorage::Dense(ref counters) => counters.memory_bytes(),
+            RamStorage::Sparse(ref table) => table.memory_bytes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storages_saturate() {
+        for num_bits in &[4usize, 20usize] {
+            let mut storage = RamStorage::new(*num_bits, CounterWidth::U8);
+            storage.set(3usize, 300u64);
+            storage.set(9usize, 7u64);
+            storage.set(9usize, 8u64);
+
+            assert_eq!(storage.get(3usize), 255u64);
+            assert_eq!(storage.get(9usize), 8u64);
+            assert_eq!(storage.get(7usize), 0u64);
+            assert_eq!(storage.entries().collect::<Vec<(usize, u64)>>().len(), 2usize);
+        }
+    }
+
+    #[test]
+    fn address_table_grows() {
+        let mut storage = RamStorage::new(32usize, CounterWidth::U32);
+        for address in 0..1000usize {
+            storage.set(address * 7919usize, address as u64 + 1u64);
+        }
+
+        for address in 0..1000usize {
+            assert_eq!(storage.get(address * 7919usize), address as u64 + 1u64);
+        }
+        assert_eq!(storage.get(1usize), 0u64);
+        assert_eq!(storage.entries().count(), 1000usize);
+
+        // Zeroed addresses leave no entry behind and the others stay reachable
+        for address in (0..1000usize).filter(|address| address % 3usize == 0usize) {
+            storage.set(address * 7919usize, 0u64);
+        }
+        storage.set(1usize, 0u64);
+        for address in 0..1000usize {
+            let expected = if address % 3usize == 0usize { 0u64 } else { address as u64 + 1u64 };
+            assert_eq!(storage.get(address * 7919usize), expected);
+        }
+        assert_eq!(storage.entries().count(), 666usize);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::hash_map::HashMap;
+use std::mem;
+use super::discriminator::ram_retina_positions;
+
+/// How the estimates of the rams recognising a retina are combined into a prediction
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Aggregation {
+    Mean,
+    Median,
+    /// Power mean of the estimates, which must not be negative for fractional exponents
+    PowerMean { exponent: f64 },
+}
+
+/// Ram keeping the sum and count of the targets trained at each address
+#[derive(Serialize, Deserialize)]
+struct RegressionRam {
+    data: HashMap<usize, (f64, u64)>,
+}
+
+impl RegressionRam {
+    fn new() -> RegressionRam {
+        RegressionRam {
+            data: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, address: usize, target: f64) {
+        let entry = self.data.entry(address).or_insert((0f64, 0u64));
+        entry.0 += target;
+        entry.1 += 1u64;
+    }
+
+    /// Mean of the targets trained at the address, if any
+    fn estimate(&self, address: usize) -> Option<f64> {
+        self.data.get(&address).map(|&(sum, count)| sum / count as f64)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.data.capacity() * (mem::size_of::<usize>() + mem::size_of::<(f64, u64)>())
+    }
+}
+
+/// Regression WiSARD (ReW), predicting a continuous target from a retina
+///
+/// Every ram keeps a running sum and count of the targets trained at each address, so its estimate
+/// for a retina is the mean target of the trained retinas sharing its address.
+#[derive(Serialize, Deserialize)]
+pub struct RegressionWisard {
+    retina_size: usize,
+    aggregation: Aggregation,
+    rams: Vec<RegressionRam>,
+    ram_retina_positions: Vec<Vec<usize>>,
+}
+
+impl RegressionWisard {
+    /// Rams read the retina positions in the order of the address mapping, usually the one of a
+    /// classification WiSARD so both follow the same seed
+    pub fn new(ram_num_bits: usize, ram_address_mapping: &[usize]) -> RegressionWisard {
+        if ram_num_bits > 62usize {
+            panic!("WiSARD ERROR: Representation overflow due to number of bits");
+        }
+        let retina_size = ram_address_mapping.len();
+        let ram_retina_positions = ram_retina_positions(retina_size, ram_num_bits, ram_address_mapping);
+
+        RegressionWisard {
+            retina_size,
+            aggregation: Aggregation::Mean,
+            rams: ram_retina_positions.iter().map(|_| RegressionRam::new()).collect(),
+            ram_retina_positions,
+        }
+    }
+
+    /// Selects how the ram estimates are combined, the default is `Aggregation::Mean`
+    pub fn set_aggregation(&mut self, aggregation: Aggregation) {
+        self.aggregation = aggregation;
+    }
+
+    pub fn train(&mut self, retina: &[bool], target: f64) {
+        if retina.len() != self.retina_size {
+            panic!("WiSARD Error: Retina size doesn't match the regression WiSARD!");
+        }
+        if !target.is_finite() {
+            panic!("WiSARD Error: Regression targets must be finite!");
+        }
+
+        let addresses = self.addresses(retina);
+        for (ram, address) in self.rams.iter_mut().zip(addresses) {
+            ram.insert(address, target);
+        }
+    }
+
+    /// Target estimated from the rams that were trained at the addresses of the retina, None when
+    /// no ram was
+    // usize::is_multiple_of needs a newer compiler than the crate otherwise does
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn predict(&self, retina: &[bool]) -> Option<f64> {
+        let mut estimates: Vec<f64> = self.rams
+                                          .iter()
+                                          .zip(self.addresses(retina))
+                                          .filter_map(|(ram, address)| ram.estimate(address))
+                                          .collect();
+        if estimates.is_empty() {
+            return None;
+        }
+        let count = estimates.len() as f64;
+
+        let prediction = match self.aggregation {
+            Aggregation::Mean => estimates.iter().sum::<f64>() / count,
+            Aggregation::Median => {
+                estimates.sort_by(|first, second| first.total_cmp(second));
+                let middle = estimates.len() / 2usize;
+                if estimates.len() % 2usize == 0usize {
+                    (estimates[middle - 1usize] + estimates[middle]) / 2f64
+                } else {
+                    estimates[middle]
+                }
+            }
+            Aggregation::PowerMean { exponent } => {
+                if exponent == 0f64 {
+                    // The limit of the power mean, the geometric mean
+                    (estimates.iter().map(|estimate| estimate.ln()).sum::<f64>() / count).exp()
+                } else {
+                    (estimates.iter().map(|estimate| estimate.powf(exponent)).sum::<f64>() / count).powf(1f64 / exponent)
+                }
+            }
+        };
+
+        Some(prediction)
+    }
+
+    /// Heap memory taken by the rams and their retina positions
+    pub fn memory_bytes(&self) -> usize {
+        self.rams.iter().map(|ram| ram.memory_bytes()).sum::<usize>()
+            + self.ram_retina_positions.iter().map(|positions| positions.capacity() * mem::size_of::<usize>()).sum::<usize>()
+    }
+
+    /// Address each ram reads from a retina
+    fn addresses(&self, retina: &[bool]) -> Vec<usize> {
+        self.ram_retina_positions
+            .iter()
+            .map(
+                |positions|
+                    positions.iter()
+                             .enumerate()
+                             .filter(|&(_, position)| retina[*position])
+                             .map(|(bit_index, _)| 1usize << bit_index)
+                             .sum()
+            ).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregations_combine_ram_estimates() {
+        let mut wisard = RegressionWisard::new(2usize, &[0usize, 1usize, 2usize, 3usize]);
+        wisard.train(&[true, true, false, false], 2f64);
+        wisard.train(&[true, true, true, true], 4f64);
+        wisard.train(&[false, false, true, true], 8f64);
+
+        assert_eq!(wisard.predict(&[false, true, false, true]), None);
+
+        // The first ram averages 2 and 4, the second one 4 and 8
+        let retina = [true, true, true, true];
+        assert_eq!(wisard.predict(&retina), Some(4.5f64));
+        wisard.set_aggregation(Aggregation::Median);
+        assert_eq!(wisard.predict(&retina), Some(4.5f64));
+        wisard.set_aggregation(Aggregation::PowerMean { exponent: 1f64 });
+        assert_eq!(wisard.predict(&retina), Some(4.5f64));
+        wisard.set_aggregation(Aggregation::PowerMean { exponent: 0f64 });
+        assert!((wisard.predict(&retina).unwrap() - 18f64.sqrt()).abs() < 1e-12);
+
+        // Only the first ram recognises the retina
+        wisard.set_aggregation(Aggregation::Mean);
+        assert_eq!(wisard.predict(&[true, true, false, true]), Some(3f64));
+    }
+
+    #[test]
+    fn median_of_an_odd_count_is_the_middle_estimate() {
+        let mut wisard = RegressionWisard::new(1usize, &[0usize, 1usize, 2usize]);
+        wisard.train(&[false, true, true], 1f64);
+        wisard.train(&[true, false, true], 4f64);
+        wisard.train(&[true, true, false], 13f64);
+
+        // Each ram only saw one target at the address of the empty retina
+        let retina = [false, false, false];
+        assert_eq!(wisard.predict(&retina), Some(6f64));
+        wisard.set_aggregation(Aggregation::Median);
+        assert_eq!(wisard.predict(&retina), Some(4f64));
+    }
+
+    #[test]
+    #[should_panic(expected = "Regression targets must be finite")]
+    fn non_finite_targets_are_rejected() {
+        RegressionWisard::new(1usize, &[0usize, 1usize]).train(&[true, false], f64::NAN);
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+/// What the rams of a class hold
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassStatistics {
+    pub class_name: String,
+    /// Examples trained minus examples forgotten
+    pub trained_examples: u64,
+    pub rams_count: usize,
+    /// Addresses holding a non zero counter in each ram
+    pub occupied_addresses: Vec<usize>,
+    /// Fraction of all the addresses of the rams holding a non zero counter
+    pub occupancy: f64,
+    /// How many addresses hold each counter value, zero left out
+    pub counter_histogram: BTreeMap<u64, usize>,
+    /// Counters stuck at the largest value their width holds
+    pub saturated_counters: usize,
+    pub memory_bytes: usize,
+    /// Sub discriminators the class learned with, more than one only when clustering
+    pub discriminators_count: usize,
+}
+
+/// What the rams of every class hold, with totals over all classes
+#[derive(Clone, Debug, PartialEq)]
+pub struct WisardStatistics {
+    /// Sorted by class name
+    pub classes: Vec<ClassStatistics>,
+    pub trained_examples: u64,
+    pub rams_count: usize,
+    pub occupied_addresses: usize,
+    pub counter_histogram: BTreeMap<u64, usize>,
+    pub saturated_counters: usize,
+    /// Rams of every class and the address mapping
+    pub memory_bytes: usize,
+}
+
+impl WisardStatistics {
+    pub fn new(classes: Vec<ClassStatistics>, memory_bytes: usize) -> WisardStatistics {
+        let mut counter_histogram = BTreeMap::new();
+        for class in &classes {
+            for (value, count) in &class.counter_histogram {
+                *counter_histogram.entry(*value).or_insert(0usize) += *count;
+            }
+        }
+
+        WisardStatistics {
+            trained_examples: classes.iter().map(|class| class.trained_examples).sum(),
+            rams_count: classes.iter().map(|class| class.rams_count).sum(),
+            occupied_addresses: classes.iter().map(|class| class.occupied_addresses.iter().sum::<usize>()).sum(),
+            saturated_counters: classes.iter().map(|class| class.saturated_counters).sum(),
+            counter_histogram,
+            memory_bytes,
+            classes,
+        }
+    }
+}
@@ -1,24 +1,58 @@
 extern crate dictawav;
+extern crate rand;
 
 use std::collections::{HashMap, HashSet};
 
-use dictawav::DictaWav;
+use dictawav::{Augmentation, Augmenter, BenchmarkNoise, BleachingPolicy, CanvasEncoding, Clustering, CounterWidth, DictaWav, DistanceMetric, Ensemble, EnsembleVoting, FrontEnd, KernelLearning, NearestKernelSearch, NoiseBenchmark, NoiseColor, PaintingMode};
+use rand::Rng;
+use std::fs;
 use std::path::PathBuf;
 use std::f64;
 
 // KernelCanvas parameters
 const KERNELS_COUNT: usize = 2048;
-const KERNELS_DIMENSION: usize = 13;
 const OUTPUT_FACTOR: usize = 10;
+const TIME_SEGMENTS: usize = 1;
+// Fitted kernels, covariance and distance thresholds are only learned from the training folds, the
+// model being rebuilt for every fold
+const KERNEL_LEARNING: KernelLearning = KernelLearning::Random;
+// Random kernels and this learning are compared before the tests by how many kernels the first four
+// files of every word activate when true, which trains two more full models
+const KERNEL_ACTIVATION_COMPARISON: bool = false;
+const COMPARED_KERNEL_LEARNING: KernelLearning = KernelLearning::KMeansPlusPlus { iterations: 5, seed: 1 };
+const NEAREST_KERNEL_SEARCH: NearestKernelSearch = NearestKernelSearch::FlatScan;
+const DISTANCE_METRIC: DistanceMetric = DistanceMetric::Euclidean;
+const PAINTING_MODE: PaintingMode = PaintingMode::Nearest;
+const CANVAS_ENCODING: CanvasEncoding = CanvasEncoding::Repetition;
 
 // WiSARD parameters
-const RETINA_SIZE: usize = KERNELS_COUNT * OUTPUT_FACTOR;
+const RETINA_SIZE: usize = KERNELS_COUNT * TIME_SEGMENTS * OUTPUT_FACTOR;
 const RAM_NUM_BITS: usize = 32;
 const USE_BLEACHING: bool = true;
 const MINIMUM_CONFIDENCE: f64 = 0.002;
 const BLEACHING_THRESHOLD: u64 = 1;
+const BLEACHING_POLICY: BleachingPolicy = BleachingPolicy::MinimumConfidence;
 const RANDOMIZE_POSITIONS: bool = true;
 const IS_CUMULATIVE: bool = true;
+const RAM_COUNTER_WIDTH: CounterWidth = CounterWidth::U32;
+// Several discriminators per class (ClusWiSARD) when Some
+const CLUSTERING: Option<Clustering> = None;
+
+// Models trained with different seeds and how they vote, a single one is just the plain model
+const ENSEMBLE_MEMBERS: usize = 1;
+const ENSEMBLE_VOTING: EnsembleVoting = EnsembleVoting::ScoreAveraging;
+
+// Augmented copies trained along with every training file, never with the tested ones
+const AUGMENTATION_COPIES: usize = 0;
+
+// Tested files are also classified with white, pink and babble noise at each ratio when true, the
+// accuracies being printed and written to the CSV file
+const NOISE_BENCHMARK: bool = false;
+const NOISE_BENCHMARK_SNRS_DB: [f64; 5] = [20.0, 15.0, 10.0, 5.0, 0.0];
+const NOISE_BENCHMARK_CSV: &str = "noise_benchmark.csv";
+
+// Where the class heatmaps of the trained model are written, nothing is written when None
+const DIAGNOSTICS_DIRECTORY: Option<&str> = None;
 
 fn main() {
     let words = vec![
@@ -35,6 +69,8 @@ fn main() {
 
 
     let mut classification_paths: HashMap<&str, HashSet<PathBuf>> = HashMap::new();
+    // The first four files of every word, as one fold would train with
+    let mut comparison_paths = Vec::with_capacity(words.len() * 4);
 
     for word in words {
         for file_number in 1..6 {
@@ -43,15 +79,43 @@ fn main() {
             path.push(word);
             path.push(file_number.to_string());
             path.set_extension("wav");
-            let mut paths = classification_paths.entry(word).or_insert_with(||HashSet::with_capacity(5));
+            let paths = classification_paths.entry(word).or_insert_with(||HashSet::with_capacity(5));
             paths.insert(path.clone());
+            if file_number < 5 {
+                comparison_paths.push(path);
+            }
         }
     }
 
+    if KERNEL_ACTIVATION_COMPARISON {
+        let seed = rand::thread_rng().gen::<usize>();
+        for &kernel_learning in &[KernelLearning::Random, COMPARED_KERNEL_LEARNING] {
+            let mut dictawav = new_model(seed, kernel_learning, &comparison_paths);
+            for path in &comparison_paths {
+                dictawav.train(path, String::from(path.parent().unwrap().file_name().unwrap().to_str().unwrap()));
+            }
+            println!("Kernels activated by the training files with {:?}: {} of {}", kernel_learning, dictawav.activated_kernels_count(), dictawav.kernels_count());
+        }
+    }
+
+    let mut noise_benchmark = if NOISE_BENCHMARK {
+        let babble_files = classification_paths.iter()
+                                               .flat_map(|(word, paths)| paths.iter().map(move |path| (path.clone(), String::from(*word))))
+                                               .collect();
+        Some(NoiseBenchmark::new(
+            vec![BenchmarkNoise::White, BenchmarkNoise::Pink, BenchmarkNoise::Babble { talkers: 4 }],
+            NOISE_BENCHMARK_SNRS_DB.to_vec(),
+            babble_files,
+            rand::thread_rng().gen::<usize>(),
+        ).unwrap())
+    } else {
+        None
+    };
+
     let mut accuracies = Vec::with_capacity(10);
     let num_tests = 10;
     for _ in 0..num_tests {
-        accuracies.push(run_tests_kfold(classification_paths.clone()));
+        accuracies.push(run_tests_kfold(classification_paths.clone(), noise_benchmark.as_mut()));
     }
 
     let mean = accuracies.iter().sum::<f64>() / f64::from(num_tests);
@@ -59,14 +123,21 @@ fn main() {
 
     println!("Total accuracy on {} tests: {}%", num_tests, 100.0 * mean);
     println!("Standard deviation on {} tests: {}%", num_tests, 100.0 * standard_deviation);
-}
 
-fn run_tests_kfold(classification_paths: HashMap<&str, HashSet<PathBuf>>) -> f64 {
+    if let Some(noise_benchmark) = noise_benchmark {
+        println!("Accuracy with noise on {} tests:\n{}", num_tests, noise_benchmark.table());
+        noise_benchmark.write_csv(fs::File::create(NOISE_BENCHMARK_CSV).unwrap()).unwrap();
+    }
+}
 
+/// Model with its kernels, covariance and distance thresholds fitted on the training files when the
+/// settings learn them
+fn new_model(seed: usize, kernel_learning: KernelLearning, training_paths: &[PathBuf]) -> DictaWav {
     let mut dictawav = DictaWav::new(
+        FrontEnd::mfcc(),
         KERNELS_COUNT,
-        KERNELS_DIMENSION,
         OUTPUT_FACTOR,
+        TIME_SEGMENTS,
         RETINA_SIZE,
         RAM_NUM_BITS,
         USE_BLEACHING,
@@ -75,6 +146,51 @@ fn run_tests_kfold(classification_paths: HashMap<&str, HashSet<PathBuf>>) -> f64
         RANDOMIZE_POSITIONS,
         IS_CUMULATIVE
     );
+    dictawav.set_seed(seed);
+    dictawav.set_nearest_kernel_search(NEAREST_KERNEL_SEARCH);
+    dictawav.set_ram_counter_width(RAM_COUNTER_WIDTH);
+    dictawav.set_bleaching_policy(BLEACHING_POLICY);
+    dictawav.set_clustering(CLUSTERING);
+    dictawav.set_painting_mode(PAINTING_MODE);
+    dictawav.set_canvas_encoding(CANVAS_ENCODING);
+    if AUGMENTATION_COPIES != 0 {
+        dictawav.set_augmentation(Some(Augmenter::new(vec![
+            Augmentation::Tempo { min_factor: 0.9, max_factor: 1.1 },
+            Augmentation::PitchShift { min_semitones: -1.0, max_semitones: 1.0 },
+            Augmentation::Gain { min_db: -6.0, max_db: 6.0 },
+            Augmentation::TimeShift { max_seconds: 0.05 },
+            Augmentation::Noise { color: NoiseColor::Pink, min_snr_db: 20.0, max_snr_db: 30.0 },
+        ], AUGMENTATION_COPIES, seed)));
+    }
+
+    if DISTANCE_METRIC == DistanceMetric::Mahalanobis {
+        dictawav.fit_covariance(training_paths.iter());
+    }
+    dictawav.set_distance_metric(DISTANCE_METRIC);
+    if kernel_learning != KernelLearning::Random {
+        dictawav.learn_kernels(training_paths.iter(), kernel_learning);
+    }
+    if CANVAS_ENCODING == CanvasEncoding::DistanceThresholds {
+        dictawav.fit_distance_thresholds(training_paths.iter());
+    }
+
+    dictawav
+}
+
+/// Whether the model learns anything from the training files besides the WiSARD
+fn fits_features() -> bool {
+    KERNEL_LEARNING != KernelLearning::Random
+        || DISTANCE_METRIC == DistanceMetric::Mahalanobis
+        || CANVAS_ENCODING == CanvasEncoding::DistanceThresholds
+}
+
+fn new_ensemble(first_seed: usize, training_paths: &[PathBuf]) -> Ensemble {
+    let members = (0..ENSEMBLE_MEMBERS).map(|member| new_model(first_seed.wrapping_add(member), KERNEL_LEARNING, training_paths))
+                                       .collect();
+    Ensemble::new(members, ENSEMBLE_VOTING)
+}
+
+fn run_tests_kfold(classification_paths: HashMap<&str, HashSet<PathBuf>>, mut noise_benchmark: Option<&mut NoiseBenchmark>) -> f64 {
 
     let total_words_per_fold = classification_paths.len();
     // 5 folds, each one with 1 path from each word
@@ -87,42 +203,99 @@ fn run_tests_kfold(classification_paths: HashMap<&str, HashSet<PathBuf>>) -> f64
     ];
 
     let mut summed_accuracy = 0f64;
+    let mut summed_member_accuracies = [0f64; ENSEMBLE_MEMBERS];
     let num_folds = folds.len();
 
+    // Each run draws its own seeds, so repeated runs still differ
+    let first_seed = rand::thread_rng().gen::<usize>();
+
     for (word, file_paths) in classification_paths {
         for (index, file_path) in file_paths.into_iter().enumerate() {
             folds[index].insert(String::from(word), file_path);
         }
     }
 
-    // Training all examples
-    for fold in &folds {
-        for (word, file_path) in fold {
-            dictawav.train(file_path.clone(), word.clone());
+    // Training all examples, unless a model fitted on the training folds is built for each fold
+    let mut ensemble = None;
+    if !fits_features() {
+        let mut trained_ensemble = new_ensemble(first_seed, &[]);
+        for fold in &folds {
+            for (word, file_path) in fold {
+                trained_ensemble.train(file_path.clone(), word.clone());
+            }
         }
+        ensemble = Some(trained_ensemble);
     }
 
     // K-Fold cross validation
-    for current_testing_fold in folds {
-        for (word, file_path) in &current_testing_fold {
-            dictawav.forget(file_path.clone(), word);
-        }
+    for testing_index in 0..num_folds {
+        let current_testing_fold = &folds[testing_index];
+        let ensemble = if fits_features() {
+            let training_files: Vec<(&String, &PathBuf)> = folds.iter()
+                                                                .enumerate()
+                                                                .filter(|&(index, _)| index != testing_index)
+                                                                .flat_map(|(_, fold)| fold.iter())
+                                                                .collect();
+            let training_paths: Vec<PathBuf> = training_files.iter().map(|&(_, file_path)| file_path.clone()).collect();
+            let mut fitted_ensemble = new_ensemble(first_seed, &training_paths);
+            for (word, file_path) in training_files {
+                fitted_ensemble.train(file_path.clone(), word.clone());
+            }
+            ensemble.insert(fitted_ensemble)
+        } else {
+            let trained_ensemble = ensemble.as_mut().unwrap();
+            for (word, file_path) in current_testing_fold {
+                trained_ensemble.forget(file_path.clone(), word).unwrap();
+            }
+            trained_ensemble
+        };
 
         let mut got_right = 0usize;
-        for (word, file_path) in &current_testing_fold {
-            if *word == dictawav.classify(file_path.clone()) {
+        let mut members_got_right = [0usize; ENSEMBLE_MEMBERS];
+        for (word, file_path) in current_testing_fold {
+            let prediction = ensemble.predict(file_path.clone());
+            if *word == prediction.class_name {
                 got_right += 1;
             }
+            for (member_got_right, member) in members_got_right.iter_mut().zip(prediction.members) {
+                if *word == member.class_name {
+                    *member_got_right += 1;
+                }
+            }
+            if let Some(ref mut noise_benchmark) = noise_benchmark {
+                noise_benchmark.evaluate(file_path, word, |samples, sample_rate| ensemble.predict_audio(samples, sample_rate).class_name)
+                               .unwrap();
+            }
         }
         summed_accuracy += got_right as f64 / total_words_per_fold as f64;
+        for (summed_member_accuracy, member_got_right) in summed_member_accuracies.iter_mut().zip(members_got_right) {
+            *summed_member_accuracy += member_got_right as f64 / total_words_per_fold as f64;
+        }
 
-        for (word, file_path) in current_testing_fold {
-            dictawav.train(file_path.clone(), word.clone());
+        if !fits_features() {
+            for (word, file_path) in current_testing_fold {
+                ensemble.train(file_path.clone(), word.clone());
+            }
         }
     }
 
+    let ensemble = ensemble.unwrap();
+    if let Some(directory) = DIAGNOSTICS_DIRECTORY {
+        ensemble.members()[0].save_class_heatmaps(directory).unwrap();
+    }
+
     let accuracy = summed_accuracy / num_folds as f64;
     println!("Got {}% of accuracy", accuracy * 100.0);
+    if ENSEMBLE_MEMBERS > 1 {
+        let member_accuracies: Vec<String> = summed_member_accuracies.iter()
+                                                                     .map(|summed| format!("{:.2}%", summed / num_folds as f64 * 100.0))
+                                                                     .collect();
+        println!("Ensemble members got {} of accuracy", member_accuracies.join(", "));
+    }
+    for member in ensemble.members() {
+        println!("Kernels ever activated: {} of {}", member.activated_kernels_count(), member.kernels_count());
+    }
+    println!("Model memory: {:.1} KiB", ensemble.memory_bytes() as f64 / 1024f64);
 
     accuracy
 }